@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::*;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Biome {
     Ocean,
     Beach,
@@ -22,3 +24,19 @@ impl Biome {
         }
     }
 }
+
+impl std::str::FromStr for Biome {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Ocean" => Ok(Biome::Ocean),
+            "Beach" => Ok(Biome::Beach),
+            "Lake" => Ok(Biome::Lake),
+            "Forest" => Ok(Biome::Forest),
+            "Hills" => Ok(Biome::Hills),
+            "MagicForest" => Ok(Biome::MagicForest),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::SystemTime;
+
+use super::*;
+
+/// How often (in seconds) the config file is checked for changes.
+const CONFIG_POLL_INTERVAL: f64 = 0.5;
+
+pub fn load_generator(path: &str) -> Result<WorldGenerator<Biome>, ConfigError> {
+    let config = GeneratorConfig::load(path)?;
+    let mut generator: WorldGenerator<Biome> = config.build()?;
+    generator.set_chunk_size(Vector2::new(CHUNK_SIZE, CHUNK_SIZE));
+    let fingerprint = generator.fingerprint();
+    generator.set_storage(Some(ChunkStorage::new(storage_directory(), fingerprint)));
+    generator.set_cache_policy(CachePolicy {
+        max_chunks: Some(MAX_CHUNKS),
+        max_bytes: None,
+        eviction: Eviction::FarthestFromFocus,
+    });
+    Ok(generator)
+}
+
+/// Where the generated chunks are stored between the runs.
+fn storage_directory() -> std::path::PathBuf {
+    std::env::temp_dir().join("island-generation-viewer")
+}
+
+/// Watches the modification time of the config file.
+pub struct ConfigWatcher {
+    path: &'static str,
+    modified: Option<SystemTime>,
+    next_poll: f64,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            modified: modified_time(path),
+            next_poll: CONFIG_POLL_INTERVAL,
+        }
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Returns `true` if the file has changed since the last check.
+    pub fn poll(&mut self, delta_time: f64) -> bool {
+        self.next_poll -= delta_time;
+        if self.next_poll > 0.0 {
+            return false;
+        }
+        self.next_poll = CONFIG_POLL_INTERVAL;
+
+        let modified = modified_time(self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -5,18 +5,33 @@ use geng::Camera2d;
 use generation::*;
 
 mod biome;
+mod config;
 mod init;
 mod renderer;
 
 use biome::*;
+use config::*;
 use renderer::*;
 
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/generator.ron");
+
 const CAMERA_ZOOM_SPEED: f32 = 0.5;
 const CAMERA_FOV_MIN: f32 = 10.0;
 const CAMERA_FOV_MAX: f32 = 1000.0;
 
 const TILE_SIZE_MIN: f32 = 0.25;
 const TILE_SIZE_MAX: f32 = 10.0;
+/// Scales with tiles at least this big are supersampled, so that they look like the detailed ones.
+const SUPERSAMPLE_TILE_SIZE: f32 = 4.0;
+
+/// How far the nearest biome is searched for. The search runs on the UI thread,
+/// and with erosion every chunk on the way is eroded, so the distance is kept small.
+const GO_TO_MAX_DISTANCE: f32 = 500.0;
+
+/// Number of tiles along each side of a chunk, every chunk is rendered as a texture.
+const CHUNK_SIZE: usize = 128;
+/// How many generated chunks are kept around the camera, the visible ones are always kept.
+const MAX_CHUNKS: usize = 100;
 
 fn main() {
     logger::init().unwrap();
@@ -33,16 +48,101 @@ struct GenerationState {
     ui_camera: Camera2d,
     framebuffer_size: Vec2<f32>,
     generator: WorldGenerator<Biome>,
+    /// Tells which chunk textures have to be drawn again.
+    chunk_events: std::sync::mpsc::Receiver<ChunkEvent>,
+    config_watcher: ConfigWatcher,
+    /// The error of the last config reload, shown on the screen.
+    config_error: Option<String>,
     renderer: Renderer,
     dragging: Option<Dragging>,
 }
 
 impl GenerationState {
     fn generate_view(&mut self) {
-        let camera_view = camera_view(&self.renderer.camera, self.framebuffer_size);
-        let view = self.generator.generate_area(aabb_to_area(camera_view));
+        let camera_view = aabb_to_area(camera_view(&self.renderer.camera, self.framebuffer_size));
+        self.generator.generate_area(camera_view, 0.0);
+        self.update_textures(camera_view);
+    }
+
+    /// Draw again the chunks of the current scale, that have changed since the last call,
+    /// and draw the visible chunks, that have no texture yet.
+    fn update_textures(&mut self, camera_view: Area<f32>) {
+        for event in self.chunk_events.try_iter() {
+            if event.tile_size == self.generator.tile_size()
+                && event.supersampling == self.generator.supersampling()
+            {
+                self.renderer.remove_texture(event.chunk_pos);
+            }
+        }
+        let view = self.generator.view(camera_view);
+        self.renderer.add_missing_textures(view);
+    }
+
+    /// Request the visible area to be generated in the background
+    /// and show the chunks that are ready.
+    fn stream_view(&mut self) {
+        let camera_view = aabb_to_area(camera_view(&self.renderer.camera, self.framebuffer_size));
+        let center = self.renderer.camera.center;
+        self.generator
+            .set_focus_points(vec![Vector2::new(center.x, center.y)]);
+        self.generator
+            .request_area(camera_view, Vector2::new(center.x, center.y));
+        self.generator.cancel_requests_outside(camera_view);
+        self.generator.poll_generated();
+        self.update_textures(camera_view);
+
+        for error in self.generator.take_storage_errors() {
+            warn!("{}", error);
+        }
+    }
+
+    /// Rebuild the generator from the config file.
+    /// On error the previous generator is kept.
+    fn reload_config(&mut self) {
+        match load_generator(self.config_watcher.path()) {
+            Ok(mut generator) => {
+                // Keep the scale chosen by the user
+                let tile_size = self.generator.tile_size();
+                generator.set_scale(GenerationScale::Supersampled {
+                    x: tile_size.x,
+                    y: tile_size.y,
+                    supersampling: self.generator.supersampling(),
+                });
+                self.chunk_events = generator.subscribe();
+                self.generator = generator;
+                self.renderer.clear_textures();
+                self.config_error = None;
+                self.generate_view();
+            }
+            Err(error) => {
+                error!("{}", error);
+                // Only a single line fits on the screen, all problems are in the log
+                let message = error.to_string();
+                let mut lines = message.lines();
+                let first = lines.next().unwrap_or_default().to_owned();
+                self.config_error = Some(match lines.count() {
+                    0 => first,
+                    more => format!("{} (and {} more problems)", first, more),
+                });
+            }
+        }
+    }
 
-        self.renderer.update_textures(view);
+    /// Move the camera to the nearest tile of the biome.
+    fn go_to(&mut self, biome: Biome) {
+        let center = self.renderer.camera.center;
+        let located = self.generator.locate(
+            Vector2::new(center.x, center.y),
+            GO_TO_MAX_DISTANCE,
+            |sample| sample.tile.is_some_and(|tile| *tile.kind() == biome),
+        );
+        match located {
+            Some(located) => {
+                self.renderer.camera.center = vec2(located.position.x, located.position.y);
+                self.generate_view();
+            }
+            None => warn!("No {:?} found within {} units", biome, GO_TO_MAX_DISTANCE),
+        }
     }
 }
 
@@ -54,6 +154,13 @@ enum Dragging {
 }
 
 impl geng::State for GenerationState {
+    fn update(&mut self, delta_time: f64) {
+        if self.config_watcher.poll(delta_time) {
+            self.reload_config();
+        }
+        self.stream_view();
+    }
+
     fn handle_event(&mut self, event: geng::Event) {
         match event {
             geng::Event::Wheel { delta } => {
@@ -69,10 +176,24 @@ impl geng::State for GenerationState {
                     let max = TILE_SIZE_MAX;
                     new_scale.x = new_scale.x.clamp(min, max);
                     new_scale.y = new_scale.y.clamp(min, max);
-                    self.generator.set_scale(GenerationScale::TileSize {
-                        x: new_scale.x,
-                        y: new_scale.y,
-                    });
+                    let scale = if new_scale.x.max(new_scale.y) >= SUPERSAMPLE_TILE_SIZE {
+                        GenerationScale::Supersampled {
+                            x: new_scale.x,
+                            y: new_scale.y,
+                            supersampling: Supersampling::Majority { samples: 2 },
+                        }
+                    } else {
+                        GenerationScale::TileSize {
+                            x: new_scale.x,
+                            y: new_scale.y,
+                        }
+                    };
+                    self.generator.set_scale(scale);
+                    // Show the chunks kept from the last time this scale was used
+                    self.renderer.clear_textures();
+                    let camera_view =
+                        aabb_to_area(camera_view(&self.renderer.camera, self.framebuffer_size));
+                    self.update_textures(camera_view);
                 } else {
                     self.renderer.camera.fov -= delta as f32 * CAMERA_ZOOM_SPEED;
                     self.renderer.camera.fov = self
@@ -87,6 +208,9 @@ impl geng::State for GenerationState {
             } => {
                 self.generate_view();
             }
+            geng::Event::KeyDown { key: geng::Key::G } => {
+                self.go_to(Biome::MagicForest);
+            }
             geng::Event::MouseDown {
                 button: geng::MouseButton::Left,
                 position,
@@ -152,6 +276,18 @@ impl geng::State for GenerationState {
                 .extend_symmetric(vec2(tile_size.x, tile_size.y) / 2.0),
             Color::WHITE,
         );
+
+        if let Some(error) = &self.config_error {
+            self.geng.default_font().draw(
+                framebuffer,
+                &self.ui_camera,
+                error,
+                camera_view.top_left() + vec2(2.0, -5.0),
+                geng::TextAlign::LEFT,
+                3.0,
+                Color::RED,
+            );
+        }
     }
 }
 
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
 use super::*;
 
 pub struct Renderer {
     pub camera: Camera2d,
     geng: Geng,
-    textures: Vec<(Vec2<i32>, ugli::Texture)>,
+    /// The textures of the chunks by their positions.
+    textures: HashMap<(i32, i32), ugli::Texture>,
     chunk_size: Vec2<f32>,
     tile_size: Vec2<f32>,
 }
@@ -12,7 +15,7 @@ impl Renderer {
     pub fn new(geng: &Geng) -> Self {
         Self {
             geng: geng.clone(),
-            textures: Vec::new(),
+            textures: HashMap::new(),
             camera: Camera2d {
                 center: Vec2::ZERO,
                 rotation: 0.0,
@@ -23,14 +26,27 @@ impl Renderer {
         }
     }
 
-    pub fn update_textures(&mut self, view: GenerationView<Biome>) {
+    /// Forget all textures, e.g. when the scale or the generator changes.
+    pub fn clear_textures(&mut self) {
+        self.textures.clear();
+    }
+
+    /// Forget the texture of the chunk, so that it is drawn again if it is still generated.
+    pub fn remove_texture(&mut self, chunk_pos: ChunkPos) {
+        self.textures.remove(&(chunk_pos.x, chunk_pos.y));
+    }
+
+    /// Draw the textures of the chunks in the view, that don't have one yet.
+    pub fn add_missing_textures(&mut self, view: GenerationView<Biome>) {
         let chunk_size = vec2(view.chunk_size.x, view.chunk_size.y);
         self.chunk_size = chunk_size.map(|x| x as f32);
         self.tile_size = vec2(view.tile_size.x, view.tile_size.y);
 
-        self.textures.clear();
-
         for (chunk_pos, chunk) in view.chunks() {
+            if self.textures.contains_key(&(chunk_pos.x, chunk_pos.y)) {
+                continue;
+            }
+
             let mut texture =
                 ugli::Texture::new_with(self.geng.ugli(), chunk_size, |_| Color::WHITE);
             texture.set_filter(ugli::Filter::Nearest);
@@ -46,19 +62,20 @@ impl Renderer {
                     &mut temp_framebuffer,
                     &geng::PixelPerfectCamera,
                     AABB::point(position).extend_positive(vec2(1.0, 1.0)),
-                    biome.map(|biome| biome.color()).unwrap_or(Color::BLACK),
+                    biome
+                        .map(|biome| biome.kind().color())
+                        .unwrap_or(Color::BLACK),
                 );
             }
-            self.textures
-                .push((vec2(chunk_pos.x, chunk_pos.y), texture));
+            self.textures.insert((chunk_pos.x, chunk_pos.y), texture);
         }
     }
 
     pub fn draw(&self, framebuffer: &mut ugli::Framebuffer) {
         let chunk_size = self.chunk_size * self.tile_size;
         let chunk_size = vec2(chunk_size.x, chunk_size.y);
-        for (chunk_pos, texture) in &self.textures {
-            let offset = chunk_pos.map(|x| x as f32) * chunk_size;
+        for (&(x, y), texture) in &self.textures {
+            let offset = vec2(x as f32, y as f32) * chunk_size;
             self.geng.draw_2d().textured(
                 framebuffer,
                 &self.camera,
@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::*;
+
+/// Serializable description of a [WorldGenerator].
+///
+/// The config is stored in the [RON](https://github.com/ron-rs/ron) format:
+/// ```ron
+/// (
+///     tile_size: (x: 2.0, y: 2.0),
+///     noises: [
+///         (
+///             name: "Height",
+///             seed: 0,
+///             properties: (
+///                 min_value: -7.0,
+///                 max_value: 13.0,
+///                 scale: 100.0,
+///                 octaves: 3,
+///                 lacunarity: 2.0,
+///                 persistance: 0.5,
+///             ),
+///         ),
+///     ],
+///     generations: [
+///         (id: "Ocean", parameters: {"Height": (min: -7.0, max: 0.0)}),
+///         (
+///             id: "Forest",
+///             parameters: {"Height": (min: 0.0, max: 13.0)},
+///             children: [(id: "Clearing", parameters: {"Height": (min: 5.0, max: 6.0)})],
+///         ),
+///     ],
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorConfig {
+    pub tile_size: Vector2<f32>,
+    pub noises: Vec<NoiseConfig>,
+    #[serde(default)]
+    pub erosion: Option<ErosionConfig>,
+    pub generations: Vec<GenerationConfig>,
+    /// The file the config was loaded from, named in the errors of [GeneratorConfig::build].
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseConfig {
+    pub name: GenerationParameter,
+    pub seed: u32,
+    pub properties: MultiNoiseProperties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErosionConfig {
+    pub parameter: GenerationParameter,
+    #[serde(default)]
+    pub properties: ErosionProperties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationConfig {
+    /// Identifies the generation value, see [GeneratorConfig::build].
+    pub id: String,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub parameters: HashMap<GenerationParameter, ParameterRange>,
+    /// Sub-generations, that only apply inside of this generation.
+    #[serde(default)]
+    pub children: Vec<GenerationConfig>,
+}
+
+impl GeneratorConfig {
+    /// Load the config from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|error| ConfigError::Io {
+            path: path.to_owned(),
+            error,
+        })?;
+        let mut config = Self::parse(&source).map_err(|error| match error {
+            ConfigError::Parse {
+                path: None,
+                line,
+                column,
+                message,
+            } => ConfigError::Parse {
+                path: Some(path.to_owned()),
+                line,
+                column,
+                message,
+            },
+            error => error,
+        })?;
+        config.path = Some(path.to_owned());
+        Ok(config)
+    }
+
+    /// Parse the config from a string.
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        ron::from_str(source).map_err(|error| ConfigError::Parse {
+            path: None,
+            line: error.position.line,
+            column: error.position.col,
+            message: error.code.to_string(),
+        })
+    }
+
+    /// A hash of the config and the [GENERATION_VERSION], that stays the same
+    /// between runs and platforms.
+    /// Used to tell apart the chunks generated with different configs, see [ChunkStorage].
+    /// The config of a [WorldGenerator] can also be identified by [WorldGenerator::fingerprint].
+    pub fn hash(&self) -> u64 {
+        let serialized = ron::to_string(self).expect("Failed to serialize the config");
+        let mut hasher = StableHasher::new();
+        hasher.write_u32(GENERATION_VERSION);
+        hasher.write(serialized.as_bytes());
+        hasher.finish()
+    }
+
+    /// Build the world generator described by the config.
+    /// The generation values are parsed from their ids.
+    /// All problems of the config are collected and returned at once.
+    pub fn build<T: FromStr>(&self) -> Result<WorldGenerator<T>, ConfigError> {
+        let mut unknown = Vec::new();
+        let generations = self.generation_trees(&mut |path, id| {
+            let value = id.parse().ok();
+            if value.is_none() {
+                unknown.push(BuildError::UnknownGeneration {
+                    generation: path.clone(),
+                });
+            }
+            value
+        });
+        let problems = match generations {
+            Some(generations) => match self.builder(generations).build() {
+                Ok(world) => return Ok(world),
+                Err(problems) => problems.0,
+            },
+            None => {
+                // Check the rest of the config too
+                let generations = self.generation_trees(&mut |_, _| Some(())).unwrap();
+                let problems = self.builder(generations).build().err();
+                problems.map(|problems| problems.0).unwrap_or_default()
+            }
+        };
+        Err(ConfigError::Invalid {
+            path: self.path.clone(),
+            problems: unknown.into_iter().chain(problems).collect(),
+        })
+    }
+
+    fn builder<T>(&self, generations: Vec<GenerationTree<T>>) -> WorldGeneratorBuilder<T> {
+        let mut builder = WorldGenerator::builder().tile_size(self.tile_size);
+        for noise in &self.noises {
+            builder = builder.noise(&noise.name, noise.seed, noise.properties.clone());
+        }
+        if let Some(erosion) = &self.erosion {
+            builder = builder.erosion(&erosion.parameter, erosion.properties.clone());
+        }
+        for generation in generations {
+            builder = builder.generation_tree(generation);
+        }
+        builder
+    }
+
+    /// The generations with the values returned by `value` for their ids,
+    /// `None` if some of the values are missing.
+    fn generation_trees<T>(
+        &self,
+        value: &mut impl FnMut(&GenerationPath, &str) -> Option<T>,
+    ) -> Option<Vec<GenerationTree<T>>> {
+        let generations: Vec<_> = (self.generations.iter().enumerate())
+            .map(|(index, config)| {
+                let path = GenerationPath::top_level(index, Some(config.id.clone()));
+                generation_tree(config, path, value)
+            })
+            .collect();
+        generations.into_iter().collect()
+    }
+}
+
+/// Serialize the map with the keys sorted, so that the output is deterministic.
+fn serialize_sorted<S: Serializer>(
+    map: &HashMap<GenerationParameter, ParameterRange>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+fn generation_tree<T>(
+    config: &GenerationConfig,
+    path: GenerationPath,
+    value: &mut impl FnMut(&GenerationPath, &str) -> Option<T>,
+) -> Option<GenerationTree<T>> {
+    let parsed = value(&path, &config.id);
+    // The children are visited even without the value, so that all of them are checked
+    let children: Vec<_> = (config.children.iter().enumerate())
+        .map(|(index, child)| {
+            let path = path.child(index, Some(child.id.clone()));
+            generation_tree(child, path, value)
+        })
+        .collect();
+    let parameters = TileGeneration {
+        parameter_values: config.parameters.clone(),
+    };
+    let mut tree = GenerationTree::new(parsed?, parameters).label(&config.id);
+    tree.children = children.into_iter().collect::<Option<_>>()?;
+    Some(tree)
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Parse {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The config describes an invalid generator, all problems are listed.
+    /// The problems tell where in the config they are, see [GenerationPath].
+    Invalid {
+        path: Option<PathBuf>,
+        problems: Vec<BuildError>,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => {
+                write!(f, "Failed to read {}: {}", path.display(), error)
+            }
+            ConfigError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", display_path(path), line, column, message),
+            ConfigError::Invalid { path, problems } => {
+                // One problem per line
+                for (index, problem) in problems.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}: {}", display_path(path), problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { error, .. } => Some(error),
+            ConfigError::Invalid { problems, .. } => problems
+                .first()
+                .map(|problem| problem as &(dyn std::error::Error + 'static)),
+            _ => None,
+        }
+    }
+}
+
+fn display_path(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map_or("<config>".into(), |path| path.display().to_string())
+}
@@ -0,0 +1,167 @@
+use super::*;
+
+/// Position of a tile on the tile grid.
+/// The tile `(0, 0)` covers the world area from `(0, 0)` to `tile_size`.
+pub type TilePos = Vector2<i64>;
+
+/// Position of a chunk on the chunk grid.
+/// The chunk `(0, 0)` contains the tiles from `(0, 0)` to `chunk_size - (1, 1)`.
+pub type ChunkPos = Vector2<i32>;
+
+/// Position of a tile inside of its chunk, from `(0, 0)` to `chunk_size - (1, 1)`.
+pub type LocalPos = Vector2<usize>;
+
+impl TilePos {
+    /// The tile containing the world position, the tiles are rounded down,
+    /// so e.g. the position `-0.5` is in the tile `-1`.
+    /// The positions beyond the range of [TilePos] are clamped to it.
+    pub fn from_world(position: Vector2<f32>, tile_size: Vector2<f32>) -> Self {
+        Vector2::new(
+            (position.x / tile_size.x).floor() as i64,
+            (position.y / tile_size.y).floor() as i64,
+        )
+    }
+
+    /// The world area covered by the tile.
+    pub fn world_area(self, tile_size: Vector2<f32>) -> Area<f32> {
+        let start = self.map(|x| x as f32) * tile_size;
+        Area {
+            start,
+            end: start + tile_size,
+        }
+    }
+
+    /// The world position of the center of the tile.
+    pub fn world_center(self, tile_size: Vector2<f32>) -> Vector2<f32> {
+        (self.map(|x| x as f32) + Vector2::new(0.5, 0.5)) * tile_size
+    }
+
+    /// The chunk containing the tile and the position of the tile inside of that chunk.
+    /// Works the same way for negative positions, e.g. with the chunk size of 50
+    /// the tile `-1` is the local tile `49` of the chunk `-1`.
+    ///
+    /// Panics if the chunk position does not fit into [ChunkPos].
+    pub fn to_chunk(self, chunk_size: Vector2<usize>) -> (ChunkPos, LocalPos) {
+        let size = chunk_size.map(to_i64);
+        let chunk = |tile: i64, size: i64| {
+            i32::try_from(tile.div_euclid(size)).unwrap_or_else(|_| {
+                panic!(
+                    "The tile {} is too far away for the chunk size {}",
+                    tile, size
+                )
+            })
+        };
+        // The remainder is in the range from 0 to the chunk size, so it fits
+        (
+            Vector2::new(chunk(self.x, size.x), chunk(self.y, size.y)),
+            Vector2::new(
+                self.x.rem_euclid(size.x) as usize,
+                self.y.rem_euclid(size.y) as usize,
+            ),
+        )
+    }
+
+    /// The inverse of [TilePos::to_chunk].
+    ///
+    /// Panics if the tile position does not fit into [TilePos].
+    pub fn from_chunk(chunk: ChunkPos, local: LocalPos, chunk_size: Vector2<usize>) -> Self {
+        let tile = |chunk: i32, local: usize, size: usize| {
+            i64::from(chunk)
+                .checked_mul(to_i64(size))
+                .and_then(|start| start.checked_add(to_i64(local)))
+                .unwrap_or_else(|| {
+                    panic!("The tile {} of the chunk {} is out of range", local, chunk)
+                })
+        };
+        Vector2::new(
+            tile(chunk.x, local.x, chunk_size.x),
+            tile(chunk.y, local.y, chunk_size.y),
+        )
+    }
+}
+
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).unwrap_or_else(|_| panic!("{} does not fit into a tile position", value))
+}
+
+impl ChunkPos {
+    /// The chunk containing the tile.
+    pub fn containing(tile: TilePos, chunk_size: Vector2<usize>) -> Self {
+        tile.to_chunk(chunk_size).0
+    }
+
+    /// The tiles of the chunk: `start` is the first tile and `end` is the first tile after the chunk.
+    pub fn tiles(self, chunk_size: Vector2<usize>) -> Area<i64> {
+        let start = TilePos::from_chunk(self, Vector2::new(0, 0), chunk_size);
+        Area {
+            start,
+            end: start + chunk_size.map(|x| x as i64),
+        }
+    }
+
+    /// The world area covered by the chunk.
+    pub fn world_area(self, chunk_size: Vector2<usize>, tile_size: Vector2<f32>) -> Area<f32> {
+        let tiles = self.tiles(chunk_size);
+        Area {
+            start: tiles.start.world_area(tile_size).start,
+            end: tiles.end.world_area(tile_size).start,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE_SIZE: Vector2<f32> = Vector2::new(2.0, 4.0);
+    const CHUNK_SIZE: Vector2<usize> = Vector2::new(50, 10);
+
+    #[test]
+    fn negative_world_positions_are_rounded_down() {
+        let tile = |x, y| TilePos::from_world(Vector2::new(x, y), TILE_SIZE);
+        assert_eq!(tile(-0.5, -0.5), Vector2::new(-1, -1));
+        assert_eq!(tile(0.0, 0.0), Vector2::new(0, 0));
+        assert_eq!(tile(-2.0, -4.0), Vector2::new(-1, -1));
+        assert_eq!(tile(-2.5, -4.5), Vector2::new(-2, -2));
+        assert_eq!(tile(1.9, 3.9), Vector2::new(0, 0));
+        assert_eq!(
+            TilePos::new(-1, -1).world_area(TILE_SIZE).start,
+            Vector2::new(-2.0, -4.0)
+        );
+    }
+
+    #[test]
+    fn negative_tiles_are_in_negative_chunks() {
+        let chunk = |x, y| TilePos::new(x, y).to_chunk(CHUNK_SIZE);
+        assert_eq!(chunk(-1, -1), (Vector2::new(-1, -1), Vector2::new(49, 9)));
+        assert_eq!(chunk(-50, -10), (Vector2::new(-1, -1), Vector2::new(0, 0)));
+        assert_eq!(chunk(-51, -11), (Vector2::new(-2, -2), Vector2::new(49, 9)));
+        assert_eq!(chunk(49, 9), (Vector2::new(0, 0), Vector2::new(49, 9)));
+        assert_eq!(chunk(50, 10), (Vector2::new(1, 1), Vector2::new(0, 0)));
+
+        for tile in [-101, -100, -99, -1, 0, 1, 99, 100] {
+            let tile = TilePos::new(tile, tile);
+            let (chunk, local) = tile.to_chunk(CHUNK_SIZE);
+            assert_eq!(TilePos::from_chunk(chunk, local, CHUNK_SIZE), tile);
+            let tiles = chunk.tiles(CHUNK_SIZE);
+            assert!(tiles.start.x <= tile.x && tile.x < tiles.end.x);
+            assert!(tiles.start.y <= tile.y && tile.y < tiles.end.y);
+        }
+    }
+
+    #[test]
+    fn chunk_tiles_end_at_the_next_chunk() {
+        let tiles = ChunkPos::new(-1, -1).tiles(CHUNK_SIZE);
+        assert_eq!(tiles.start, Vector2::new(-50, -10));
+        assert_eq!(tiles.end, Vector2::new(0, 0));
+        let area = ChunkPos::new(-1, 0).world_area(CHUNK_SIZE, TILE_SIZE);
+        assert_eq!(area.start, Vector2::new(-100.0, 0.0));
+        assert_eq!(area.end, Vector2::new(0.0, 40.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "too far away")]
+    fn far_tiles_do_not_wrap_around() {
+        TilePos::new(i64::MAX, 0).to_chunk(Vector2::new(1, 1));
+    }
+}
@@ -0,0 +1,214 @@
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+
+use super::*;
+
+pub(crate) struct Job<T> {
+    pub chunk_pos: ChunkPos,
+    /// Squared distance to the focus point, closer chunks are generated first.
+    pub priority: f32,
+    /// Generation epoch of the [WorldGenerator] at the moment of the request.
+    pub epoch: u64,
+    pub generator: Arc<Generator<T>>,
+    pub storage: Option<Arc<ChunkStorage<T>>>,
+    pub chunk_size: Vector2<usize>,
+    pub scale: Scale,
+    pub retain_parameters: bool,
+}
+
+impl<T> Job<T> {
+    fn key(&self) -> ChunkKey {
+        (self.scale, self.chunk_pos)
+    }
+}
+
+pub(crate) struct Finished<T> {
+    pub chunk_pos: ChunkPos,
+    pub scale: Scale,
+    pub epoch: u64,
+    /// `None` if the generation panicked, the panic is reported by the panic hook.
+    pub loaded: Option<LoadedChunk<T>>,
+}
+
+struct Queue<T> {
+    jobs: Vec<Job<T>>,
+    shutdown: bool,
+}
+
+struct Shared<T> {
+    queue: Mutex<Queue<T>>,
+    available: Condvar,
+}
+
+impl<T> Shared<T> {
+    /// The queue stays consistent even if a thread panicked while holding the lock.
+    fn queue(&self) -> MutexGuard<'_, Queue<T>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A pool of threads generating chunks in the background.
+pub(crate) struct ChunkWorkers<T> {
+    shared: Arc<Shared<T>>,
+    results: Receiver<Finished<T>>,
+    threads: Vec<JoinHandle<()>>,
+    /// Chunks that are queued or being generated, with the epoch of the request.
+    pending: HashMap<ChunkKey, u64>,
+    /// Chunks, whose generation panicked, with the epoch of the request.
+    /// They are not requested again in the same epoch, since they would fail again,
+    /// unless they are cancelled or forgotten (see [ChunkWorkers::forget_failed]).
+    failed: HashMap<ChunkKey, u64>,
+}
+
+impl<T: Copy + Send + Sync + 'static> ChunkWorkers<T> {
+    pub(crate) fn new(threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                jobs: Vec::new(),
+                shutdown: false,
+            }),
+            available: Condvar::new(),
+        });
+        let (sender, results) = mpsc::channel();
+        let threads = (0..threads.max(1))
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                let sender = sender.clone();
+                std::thread::spawn(move || work(&shared, &sender))
+            })
+            .collect();
+        Self {
+            shared,
+            results,
+            threads,
+            pending: HashMap::new(),
+            failed: HashMap::new(),
+        }
+    }
+}
+
+impl<T> ChunkWorkers<T> {
+    pub(crate) fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub(crate) fn request(&mut self, job: Job<T>) {
+        match self.failed.get(&job.key()) {
+            Some(&epoch) if epoch == job.epoch => return,
+            Some(_) => {
+                self.failed.remove(&job.key());
+            }
+            None => {}
+        }
+        if self.pending.insert(job.key(), job.epoch) == Some(job.epoch) {
+            return;
+        }
+        self.shared.queue().jobs.push(job);
+        self.shared.available.notify_one();
+    }
+
+    /// Recalculate the priorities of all queued chunks.
+    pub(crate) fn reprioritize(&mut self, priority: impl Fn(ChunkKey) -> f32) {
+        for job in &mut self.shared.queue().jobs {
+            job.priority = priority(job.key());
+        }
+    }
+
+    /// Remove the queued and the failed chunks, for which the predicate returns `false`.
+    /// Chunks that are already being generated are not affected.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(ChunkKey) -> bool) {
+        let pending = &mut self.pending;
+        self.shared.queue().jobs.retain(|job| {
+            let keep = keep(job.key());
+            if !keep {
+                pending.remove(&job.key());
+            }
+            keep
+        });
+        self.failed.retain(|&key, _| keep(key));
+    }
+
+    /// Allow the failed chunks to be requested again, e.g. after the generator has changed.
+    pub(crate) fn forget_failed(&mut self) {
+        self.failed.clear();
+    }
+
+    /// Get the chunks generated since the last call.
+    pub(crate) fn finished(&mut self) -> Vec<Finished<T>> {
+        let finished: Vec<_> = self.results.try_iter().collect();
+        for result in &finished {
+            let key = (result.scale, result.chunk_pos);
+            if self.pending.get(&key) == Some(&result.epoch) {
+                self.pending.remove(&key);
+            }
+            if result.loaded.is_none() {
+                self.failed.insert(key, result.epoch);
+            }
+        }
+        finished
+    }
+}
+
+impl<T> Drop for ChunkWorkers<T> {
+    fn drop(&mut self) {
+        {
+            let mut queue = self.shared.queue();
+            queue.shutdown = true;
+            queue.jobs.clear();
+        }
+        self.shared.available.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn work<T: Copy>(shared: &Shared<T>, sender: &Sender<Finished<T>>) {
+    loop {
+        let job = {
+            let mut queue = shared.queue();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                let nearest = queue
+                    .jobs
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.priority.total_cmp(&b.priority))
+                    .map(|(index, _)| index);
+                if let Some(index) = nearest {
+                    break queue.jobs.swap_remove(index);
+                }
+                queue = shared
+                    .available
+                    .wait(queue)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        };
+
+        // A panic fails only this chunk, the thread keeps working
+        let loaded = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            load_or_generate(
+                &job.generator,
+                job.storage.as_deref(),
+                job.chunk_pos,
+                job.chunk_size,
+                job.scale,
+                job.retain_parameters,
+            )
+        }))
+        .ok();
+        let finished = Finished {
+            chunk_pos: job.chunk_pos,
+            scale: job.scale,
+            epoch: job.epoch,
+            loaded,
+        };
+        if sender.send(finished).is_err() {
+            return;
+        }
+    }
+}
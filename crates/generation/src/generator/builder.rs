@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// A generation together with its sub-generations, see [WorldGeneratorBuilder::generation_tree].
+#[derive(Debug, Clone)]
+pub struct GenerationTree<T> {
+    pub value: T,
+    pub parameters: TileGeneration,
+    /// Sub-generations, that only apply inside of this generation.
+    pub children: Vec<GenerationTree<T>>,
+    /// Tells the generation apart in the errors, e.g. the id of a [GenerationConfig].
+    pub label: Option<String>,
+}
+
+impl<T> GenerationTree<T> {
+    pub fn new(value: T, parameters: TileGeneration) -> Self {
+        Self {
+            value,
+            parameters,
+            children: Vec::new(),
+            label: None,
+        }
+    }
+
+    /// Add a sub-generation.
+    pub fn child(mut self, child: GenerationTree<T>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_owned());
+        self
+    }
+}
+
+/// Locates a generation described by a [WorldGeneratorBuilder] or a [GeneratorConfig].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationPath {
+    /// The index of the top-level generation, followed by the indices
+    /// of the sub-generations among their siblings.
+    pub indices: Vec<usize>,
+    /// See [GenerationTree::label].
+    pub label: Option<String>,
+}
+
+impl GenerationPath {
+    pub(crate) fn top_level(index: usize, label: Option<String>) -> Self {
+        Self {
+            indices: vec![index],
+            label,
+        }
+    }
+
+    pub(crate) fn child(&self, index: usize, label: Option<String>) -> Self {
+        let mut indices = self.indices.clone();
+        indices.push(index);
+        Self { indices, label }
+    }
+}
+
+/// Formatted the same way as the generations are nested in a config,
+/// e.g. `generations[1].children[0] ("Clearing")`.
+impl std::fmt::Display for GenerationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (depth, index) in self.indices.iter().enumerate() {
+            let field = if depth == 0 {
+                "generations"
+            } else {
+                ".children"
+            };
+            write!(f, "{}[{}]", field, index)?;
+        }
+        if let Some(label) = &self.label {
+            write!(f, " ({:?})", label)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes a [WorldGenerator] to be built, see [WorldGenerator::builder].
+/// Nothing is checked until [WorldGeneratorBuilder::build].
+pub struct WorldGeneratorBuilder<T> {
+    seed: u32,
+    tile_size: Vector2<f32>,
+    supersampling: Supersampling,
+    chunk_size: Vector2<usize>,
+    noises: Vec<(GenerationParameter, u32, MultiNoiseProperties)>,
+    erosion: Option<(GenerationParameter, ErosionProperties)>,
+    generations: Vec<GenerationTree<T>>,
+    fallback: Option<T>,
+    retain_parameters: bool,
+    cache_policy: CachePolicy,
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    background_threads: Option<usize>,
+}
+
+impl<T> WorldGenerator<T> {
+    /// Start describing a world generator, for example:
+    /// ```
+    /// use generation::*;
+    ///
+    /// let world: WorldGenerator<&str> = WorldGenerator::builder()
+    ///     .seed(42)
+    ///     .tile_size(Vector2::new(1.0, 1.0))
+    ///     .noise(
+    ///         "Height",
+    ///         0,
+    ///         MultiNoiseProperties {
+    ///             min_value: -10.0,
+    ///             max_value: 10.0,
+    ///             scale: 100.0,
+    ///             octaves: 3,
+    ///             lacunarity: 2.0,
+    ///             persistance: 0.5,
+    ///         },
+    ///     )
+    ///     .generation("Ocean", TileGeneration::new(vec![("Height", -10.0..=0.0)]))
+    ///     .generation("Land", TileGeneration::new(vec![("Height", 0.0..=10.0)]))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> WorldGeneratorBuilder<T> {
+        WorldGeneratorBuilder {
+            seed: 0,
+            tile_size: DEFAULT_TILE_SIZE,
+            supersampling: Supersampling::None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            noises: Vec::new(),
+            erosion: None,
+            generations: Vec::new(),
+            fallback: None,
+            retain_parameters: false,
+            cache_policy: CachePolicy::default(),
+            thread_pool: None,
+            background_threads: None,
+        }
+    }
+}
+
+impl<T> WorldGeneratorBuilder<T> {
+    /// The seed of the world, added to the seeds of all noises.
+    /// Changing it gives a different world with the same kinds of terrain. Defaults to 0.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Defaults to [DEFAULT_TILE_SIZE].
+    /// Only changes the tile size, the supersampling set by
+    /// [WorldGeneratorBuilder::scale] is kept.
+    pub fn tile_size(mut self, tile_size: Vector2<f32>) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Set the tile size together with the supersampling, see [WorldGenerator::set_scale].
+    pub fn scale(mut self, scale: GenerationScale) -> Self {
+        (self.tile_size, self.supersampling) = scale.tile_size_and_supersampling();
+        self
+    }
+
+    /// Defaults to [DEFAULT_CHUNK_SIZE].
+    pub fn chunk_size(mut self, chunk_size: Vector2<usize>) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Add a noise, see [Generator::add_noise].
+    pub fn noise(mut self, name: &str, seed: u32, properties: MultiNoiseProperties) -> Self {
+        self.noises.push((name.to_owned(), seed, properties));
+        self
+    }
+
+    /// Erode the values of the parameter, see [Generator::set_erosion].
+    pub fn erosion(mut self, parameter: &str, properties: ErosionProperties) -> Self {
+        self.erosion = Some((parameter.to_owned(), properties));
+        self
+    }
+
+    /// Add a top-level generation without sub-generations.
+    pub fn generation(self, value: T, parameters: TileGeneration) -> Self {
+        self.generation_tree(GenerationTree::new(value, parameters))
+    }
+
+    /// Add a top-level generation together with its sub-generations.
+    pub fn generation_tree(mut self, generation: GenerationTree<T>) -> Self {
+        self.generations.push(generation);
+        self
+    }
+
+    /// The value of the tiles, that fit no generation, see [Generator::set_fallback].
+    pub fn fallback(mut self, fallback: T) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// See [WorldGenerator::set_retain_parameters].
+    pub fn retain_parameters(mut self, retain: bool) -> Self {
+        self.retain_parameters = retain;
+        self
+    }
+
+    /// See [WorldGenerator::set_cache_policy].
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Generate the chunks synchronously in the pool, see [WorldGenerator::set_thread_pool].
+    pub fn thread_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
+    /// See [WorldGenerator::set_background_threads].
+    pub fn background_threads(mut self, threads: usize) -> Self {
+        self.background_threads = Some(threads);
+        self
+    }
+
+    /// Check the description and build the generator.
+    /// All problems are collected and returned at once, see [WorldGeneratorBuilder::validate].
+    pub fn build(self) -> Result<WorldGenerator<T>, BuildErrors> {
+        let mut problems = self.validate();
+        if !problems.is_empty() {
+            return Err(BuildErrors(problems));
+        }
+
+        let mut generator = Generator::new();
+        for (index, (name, seed, properties)) in self.noises.into_iter().enumerate() {
+            if let Err(error) = generator.add_noise(&name, self.seed.wrapping_add(seed), properties)
+            {
+                problems.push(BuildError::Noise { index, error });
+            }
+        }
+        if let Some((parameter, properties)) = self.erosion {
+            if let Err(error) = generator.set_erosion(&parameter, properties) {
+                problems.push(BuildError::Erosion(error));
+            }
+        }
+        for (index, generation) in self.generations.into_iter().enumerate() {
+            let path = GenerationPath::top_level(index, generation.label.clone());
+            add_generation_tree(&mut generator, None, path, generation, &mut problems);
+        }
+        generator.set_fallback(self.fallback);
+        if !problems.is_empty() {
+            return Err(BuildErrors(problems));
+        }
+
+        let mut world = WorldGenerator::new();
+        world.set_generator(generator);
+        world.set_scale(GenerationScale::Supersampled {
+            x: self.tile_size.x,
+            y: self.tile_size.y,
+            supersampling: self.supersampling,
+        });
+        world.set_chunk_size(self.chunk_size);
+        world.set_retain_parameters(self.retain_parameters);
+        world.set_cache_policy(self.cache_policy);
+        world.set_thread_pool(self.thread_pool);
+        world.set_background_threads(self.background_threads);
+        Ok(world)
+    }
+
+    /// All problems of the description, found without building anything,
+    /// so that one invalid entry does not hide the problems of the others.
+    pub fn validate(&self) -> Vec<BuildError> {
+        let mut problems = Vec::new();
+        let Vector2 { x, y } = self.tile_size;
+        if !x.is_finite() || !y.is_finite() || x <= 0.0 || y <= 0.0 {
+            problems.push(BuildError::InvalidTileSize { x, y });
+        }
+        if let Supersampling::Majority { samples } | Supersampling::Average { samples } =
+            self.supersampling
+        {
+            if !self.supersampling.is_valid() {
+                problems.push(BuildError::InvalidSupersampling { samples });
+            }
+        }
+        let Vector2 { x, y } = self.chunk_size;
+        if x == 0 || y == 0 {
+            problems.push(BuildError::InvalidChunkSize { x, y });
+        }
+
+        for (index, (name, _, properties)) in self.noises.iter().enumerate() {
+            let mut invalid = |error| problems.push(BuildError::Noise { index, error });
+            if self.noises[..index].iter().any(|(other, ..)| other == name) {
+                invalid(GenerationError::DuplicateNoise { name: name.clone() });
+            }
+            for error in properties.problems(name) {
+                invalid(error);
+            }
+        }
+
+        if let Some((parameter, properties)) = &self.erosion {
+            if !self.has_noise(parameter) {
+                problems.push(BuildError::Erosion(GenerationError::NoiseNotFound {
+                    name: parameter.clone(),
+                }));
+            }
+            for error in properties.problems() {
+                problems.push(BuildError::Erosion(error));
+            }
+        }
+
+        for (index, generation) in self.generations.iter().enumerate() {
+            let path = GenerationPath::top_level(index, generation.label.clone());
+            self.generation_problems(generation, path, &mut problems);
+        }
+        problems
+    }
+
+    fn has_noise(&self, name: &str) -> bool {
+        self.noises.iter().any(|(noise, ..)| noise == name)
+    }
+
+    fn generation_problems(
+        &self,
+        generation: &GenerationTree<T>,
+        path: GenerationPath,
+        problems: &mut Vec<BuildError>,
+    ) {
+        let mut invalid = |error| {
+            problems.push(BuildError::Generation {
+                generation: path.clone(),
+                error,
+            })
+        };
+        let parameter_values = &generation.parameters.parameter_values;
+        let (parameters, noises) = (parameter_values.len(), self.noises.len());
+        if parameters > noises {
+            invalid(GenerationError::TooManyParameters { parameters, noises });
+        }
+        // Sorted, so that the problems are reported in the same order every time
+        for (parameter, range) in parameter_values.iter().collect::<BTreeMap<_, _>>() {
+            if !self.has_noise(parameter) {
+                invalid(GenerationError::NoiseNotFound {
+                    name: parameter.clone(),
+                });
+            }
+            if let Err(error) = range.check(parameter) {
+                invalid(error);
+            }
+        }
+
+        for (index, child) in generation.children.iter().enumerate() {
+            let path = path.child(index, child.label.clone());
+            self.generation_problems(child, path, problems);
+        }
+    }
+}
+
+fn add_generation_tree<T>(
+    generator: &mut Generator<T>,
+    parent: Option<GenerationId>,
+    path: GenerationPath,
+    generation: GenerationTree<T>,
+    problems: &mut Vec<BuildError>,
+) {
+    let result = match parent {
+        None => generator.add_generation(generation.value, generation.parameters),
+        Some(parent) => {
+            generator.add_sub_generation(parent, generation.value, generation.parameters)
+        }
+    };
+    let id = match result {
+        Ok(id) => id,
+        Err(error) => {
+            problems.push(BuildError::Generation {
+                generation: path,
+                error,
+            });
+            return;
+        }
+    };
+    for (index, child) in generation.children.into_iter().enumerate() {
+        let path = path.child(index, child.label.clone());
+        add_generation_tree(generator, Some(id), path, child, problems);
+    }
+}
+
+/// A problem with the description of a [WorldGenerator],
+/// see [WorldGeneratorBuilder::build] and [GeneratorConfig::build].
+#[derive(Debug)]
+pub enum BuildError {
+    InvalidTileSize {
+        x: f32,
+        y: f32,
+    },
+    InvalidChunkSize {
+        x: usize,
+        y: usize,
+    },
+    /// The number of samples of the [Supersampling] is out of range.
+    InvalidSupersampling {
+        samples: usize,
+    },
+    /// A problem with the noise at `index`, in the order the noises were added.
+    Noise {
+        index: usize,
+        error: GenerationError,
+    },
+    Erosion(GenerationError),
+    Generation {
+        generation: GenerationPath,
+        error: GenerationError,
+    },
+    /// The value of the generation could not be parsed from the id in the config.
+    UnknownGeneration {
+        generation: GenerationPath,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidTileSize { x, y } => {
+                write!(f, "Invalid tile size ({}, {}), it must be positive", x, y)
+            }
+            BuildError::InvalidChunkSize { x, y } => {
+                write!(f, "Invalid chunk size ({}, {}), it must be positive", x, y)
+            }
+            BuildError::InvalidSupersampling { samples } => write!(
+                f,
+                "Invalid number of samples {}, it must be from 1 to {}",
+                samples, MAX_SUPERSAMPLES
+            ),
+            BuildError::Noise { index, error } => write!(f, "noises[{}]: {}", index, error),
+            BuildError::Erosion(error) => write!(f, "erosion: {}", error),
+            BuildError::Generation { generation, error } => {
+                write!(f, "{}: {}", generation, error)
+            }
+            BuildError::UnknownGeneration { generation } => {
+                write!(f, "{}: Unknown generation id", generation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Noise { error, .. }
+            | BuildError::Erosion(error)
+            | BuildError::Generation { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// All problems found by [WorldGeneratorBuilder::build], there is at least one.
+#[derive(Debug)]
+pub struct BuildErrors(pub Vec<BuildError>);
+
+impl Deref for BuildErrors {
+    type Target = [BuildError];
+
+    fn deref(&self) -> &[BuildError] {
+        &self.0
+    }
+}
+
+impl IntoIterator for BuildErrors {
+    type Item = BuildError;
+    type IntoIter = std::vec::IntoIter<BuildError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::fmt::Display for BuildErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // One problem per line
+        for (index, problem) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BuildErrors {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0
+            .first()
+            .map(|problem| problem as &(dyn std::error::Error + 'static))
+    }
+}
@@ -0,0 +1,261 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::*;
+
+/// Limits the amount of chunks kept by a [WorldGenerator].
+/// When a limit is exceeded, the chunks are evicted in the [Eviction] order.
+/// The chunks of the area that is being generated, or was requested last
+/// with [WorldGenerator::request_area], are never evicted,
+/// so the limits might be exceeded by a large area.
+#[derive(Debug, Clone, Default)]
+pub struct CachePolicy {
+    /// Maximum number of kept chunks. `None` means no limit.
+    pub max_chunks: Option<usize>,
+    /// Maximum estimated memory used by the kept chunks. `None` means no limit.
+    pub max_bytes: Option<usize>,
+    pub eviction: Eviction,
+}
+
+/// Which chunks are evicted first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// The chunks that were not generated or viewed for the longest time.
+    #[default]
+    LeastRecentlyUsed,
+    /// The chunks furthest from the focus points, see [WorldGenerator::set_focus_points].
+    /// Without focus points behaves as [Eviction::LeastRecentlyUsed].
+    FarthestFromFocus,
+}
+
+/// The exact tile size and the supersampling of the cached chunks. The chunks of every scale
+/// are kept side by side, so that returning to a previous scale does not generate them again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Scale {
+    tile_size: [u32; 2],
+    supersampling: Supersampling,
+}
+
+impl Scale {
+    pub(crate) fn new(tile_size: Vector2<f32>, supersampling: Supersampling) -> Self {
+        Self {
+            tile_size: [tile_size.x.to_bits(), tile_size.y.to_bits()],
+            supersampling,
+        }
+    }
+
+    pub(crate) fn tile_size(self) -> Vector2<f32> {
+        Vector2::new(
+            f32::from_bits(self.tile_size[0]),
+            f32::from_bits(self.tile_size[1]),
+        )
+    }
+
+    pub(crate) fn supersampling(self) -> Supersampling {
+        self.supersampling
+    }
+}
+
+pub(crate) type ChunkKey = (Scale, ChunkPos);
+
+struct CachedChunk<T> {
+    chunk: ChunkGeneration<T>,
+    last_used: AtomicU64,
+}
+
+/// The generated chunks, together with the information needed to evict them.
+pub(crate) struct ChunkCache<T> {
+    chunks: HashMap<ChunkKey, CachedChunk<T>>,
+    bytes: usize,
+    /// Increased on every access to order the chunks by their last use.
+    clock: AtomicU64,
+}
+
+impl<T> ChunkCache<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+            bytes: 0,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub(crate) fn contains(&self, key: ChunkKey) -> bool {
+        self.chunks.contains_key(&key)
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = ChunkKey> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Get the chunk and mark it as used.
+    pub(crate) fn get(&self, key: ChunkKey) -> Option<&ChunkGeneration<T>> {
+        self.chunks.get(&key).map(|cached| {
+            let now = self.clock.fetch_add(1, Ordering::Relaxed);
+            cached.last_used.store(now, Ordering::Relaxed);
+            &cached.chunk
+        })
+    }
+
+    pub(crate) fn insert(&mut self, key: ChunkKey, chunk: ChunkGeneration<T>) {
+        self.bytes += chunk.byte_size();
+        let cached = CachedChunk {
+            chunk,
+            last_used: AtomicU64::new(*self.clock.get_mut()),
+        };
+        *self.clock.get_mut() += 1;
+        if let Some(old) = self.chunks.insert(key, cached) {
+            self.bytes -= old.chunk.byte_size();
+        }
+    }
+
+    /// Remove all chunks and return their keys.
+    pub(crate) fn clear(&mut self) -> Vec<ChunkKey> {
+        self.bytes = 0;
+        self.chunks.drain().map(|(key, _)| key).collect()
+    }
+
+    /// Remove the chunks, for which the predicate returns `false`, and return their keys.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(ChunkKey) -> bool) -> Vec<ChunkKey> {
+        let bytes = &mut self.bytes;
+        let mut removed = Vec::new();
+        self.chunks.retain(|&key, cached| {
+            let keep = keep(key);
+            if !keep {
+                *bytes -= cached.chunk.byte_size();
+                removed.push(key);
+            }
+            keep
+        });
+        removed
+    }
+
+    /// Evict the chunks until the limits of the policy are satisfied.
+    /// `distance` is used for [Eviction::FarthestFromFocus],
+    /// the chunks for which `protected` returns `true` are kept.
+    /// Returns the keys of the evicted chunks.
+    fn evict(
+        &mut self,
+        policy: &CachePolicy,
+        distance: Option<impl Fn(ChunkKey) -> f32>,
+        protected: impl Fn(ChunkKey) -> bool,
+    ) -> Vec<ChunkKey> {
+        let max_chunks = policy.max_chunks.unwrap_or(usize::MAX);
+        let max_bytes = policy.max_bytes.unwrap_or(usize::MAX);
+        if self.chunks.len() <= max_chunks && self.bytes <= max_bytes {
+            return Vec::new();
+        }
+
+        // Sort the candidates, so that the first ones are evicted first
+        let mut candidates: Vec<(ChunkKey, f32, u64)> = self
+            .chunks
+            .iter()
+            .filter(|(&key, _)| !protected(key))
+            .map(|(&key, cached)| {
+                let distance = match (&distance, policy.eviction) {
+                    (Some(distance), Eviction::FarthestFromFocus) => distance(key),
+                    _ => 0.0,
+                };
+                (key, distance, cached.last_used.load(Ordering::Relaxed))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.cmp(&b.2)));
+
+        let mut evicted = Vec::new();
+        for (key, _, _) in candidates {
+            if self.chunks.len() <= max_chunks && self.bytes <= max_bytes {
+                break;
+            }
+            if let Some(cached) = self.chunks.remove(&key) {
+                self.bytes -= cached.chunk.byte_size();
+                evicted.push(key);
+            }
+        }
+        evicted
+    }
+}
+
+impl<T> WorldGenerator<T> {
+    pub fn cache_policy(&self) -> &CachePolicy {
+        &self.cache_policy
+    }
+
+    /// Change the limits of the kept chunks. The excess chunks are evicted immediately.
+    pub fn set_cache_policy(&mut self, policy: CachePolicy) {
+        self.cache_policy = policy;
+        self.enforce_cache_policy(self.requested);
+    }
+
+    /// Set the positions (e.g. of the players or the camera), around which
+    /// the chunks are kept with [Eviction::FarthestFromFocus].
+    pub fn set_focus_points(&mut self, points: Vec<Vector2<f32>>) {
+        self.focus_points = points;
+    }
+
+    /// Number of the generated chunks that are kept, of all scales.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Estimated memory used by the generated chunks in bytes.
+    pub fn chunk_bytes(&self) -> usize {
+        self.chunks.bytes()
+    }
+
+    /// Remove the generated chunks of all scales intersecting the area.
+    pub fn unload_area(&mut self, area: Area<f32>) {
+        let chunk_size = self.chunk_size;
+        let unloaded = self.chunks.retain(|(scale, chunk_pos)| {
+            let (start, end) = world_area_chunks(area, chunk_size, scale.tile_size());
+            !chunk_in_range(chunk_pos, start, end)
+        });
+        self.emit_evicted(unloaded);
+    }
+
+    /// Keep only the generated chunks, for which the predicate returns `true`.
+    /// The predicate receives the tile size and the position of the chunk.
+    pub fn retain_chunks(&mut self, mut keep: impl FnMut(Vector2<f32>, ChunkPos) -> bool) {
+        let removed = self
+            .chunks
+            .retain(|(scale, chunk_pos)| keep(scale.tile_size(), chunk_pos));
+        self.emit_evicted(removed);
+    }
+
+    /// Evict the chunks exceeding the limits of the policy,
+    /// except for the chunks of the scale between `protected.1` and `protected.2`.
+    pub(crate) fn enforce_cache_policy(&mut self, protected: Option<(Scale, ChunkPos, ChunkPos)>) {
+        let chunk_size = self.chunk_size.map(|x| x as f32);
+        let focus_points = &self.focus_points;
+        let distance = (!focus_points.is_empty()).then_some(move |(scale, chunk_pos): ChunkKey| {
+            let center = (chunk_pos.map(|x| x as f32) + Vector2::new(0.5, 0.5))
+                * chunk_size
+                * scale.tile_size();
+            focus_points
+                .iter()
+                .map(|&point| {
+                    let delta = center - point;
+                    delta.x * delta.x + delta.y * delta.y
+                })
+                .fold(f32::INFINITY, f32::min)
+        });
+        let evicted = self
+            .chunks
+            .evict(&self.cache_policy, distance, |(scale, chunk_pos)| {
+                protected.is_some_and(|(protected, start, end)| {
+                    scale == protected && chunk_in_range(chunk_pos, start, end)
+                })
+            });
+        self.emit_evicted(evicted);
+    }
+}
+
+pub(crate) fn chunk_in_range(chunk_pos: ChunkPos, start: ChunkPos, end: ChunkPos) -> bool {
+    (start.x..=end.x).contains(&chunk_pos.x) && (start.y..=end.y).contains(&chunk_pos.y)
+}
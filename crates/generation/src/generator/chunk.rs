@@ -1,27 +1,186 @@
 use super::*;
 
-pub(crate) type Tile<T> = Option<T>;
+pub(crate) type Tile<T> = Option<TileValue<T>>;
 
-pub struct ChunkGeneration<T, const W: usize, const H: usize> {
+/// The generated value of a tile.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileValue<T> {
+    /// The top-level generation.
+    pub parent: T,
+    /// The most specific sub-generation that matched inside of the parent, if any.
+    pub child: Option<T>,
+}
+
+impl<T> TileValue<T> {
+    /// The most specific generation of the tile.
+    pub fn kind(&self) -> &T {
+        self.child.as_ref().unwrap_or(&self.parent)
+    }
+}
+
+pub struct ChunkGeneration<T> {
+    /// Number of tiles along each side.
+    size: Vector2<usize>,
     generation: Vec<Tile<T>>,
+    /// The parameter values of every tile, if they were retained.
+    /// Stored tile by tile, `parameter_count` values per tile.
+    parameters: Option<Vec<f32>>,
+    parameter_count: usize,
+    /// The eroded values of the tiles, if the generator erodes a parameter.
+    /// Kept even if the parameters are not retained, since they depend on
+    /// the surrounding tiles, unlike the rest of the parameters.
+    eroded: Option<Vec<f32>>,
 }
 
-impl<T, const W: usize, const H: usize> ChunkGeneration<T, W, H> {
-    pub(crate) fn new(generation: Vec<Tile<T>>) -> Self {
+impl<T> ChunkGeneration<T> {
+    pub(crate) fn new(
+        size: Vector2<usize>,
+        generation: Vec<Tile<T>>,
+        parameters: Option<Vec<f32>>,
+        parameter_count: usize,
+    ) -> Self {
         assert!(
-            generation.len() == W * H,
+            generation.len() == size.x * size.y,
             "Generation does not fit in the chunk. Chunk size = ({}, {}), generation length = {}",
-            W,
-            H,
+            size.x,
+            size.y,
             generation.len()
         );
-        Self { generation }
+        if let Some(parameters) = &parameters {
+            assert!(
+                parameters.len() == size.x * size.y * parameter_count,
+                "Parameters do not fit in the chunk. Chunk size = ({}, {}), parameters length = {}",
+                size.x,
+                size.y,
+                parameters.len()
+            );
+        }
+        Self {
+            size,
+            generation,
+            parameters,
+            parameter_count,
+            eroded: None,
+        }
+    }
+
+    pub(crate) fn with_eroded(mut self, eroded: Vec<f32>) -> Self {
+        assert!(
+            eroded.len() == self.generation.len(),
+            "Eroded values do not fit in the chunk. Chunk size = ({}, {}), eroded length = {}",
+            self.size.x,
+            self.size.y,
+            eroded.len()
+        );
+        self.eroded = Some(eroded);
+        self
+    }
+
+    /// Number of tiles along each side of the chunk.
+    pub fn size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    /// Whether the parameter values of the tiles were retained.
+    pub(crate) fn has_parameters(&self) -> bool {
+        self.parameters.is_some()
+    }
+
+    pub(crate) fn discard_parameters(&mut self) {
+        self.parameters = None;
+    }
+
+    /// Estimated memory used by the chunk in bytes.
+    pub(crate) fn byte_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.generation.len() * std::mem::size_of::<Tile<T>>()
+            + self.parameters.as_ref().map_or(0, |parameters| {
+                parameters.len() * std::mem::size_of::<f32>()
+            })
+            + self
+                .eroded
+                .as_ref()
+                .map_or(0, |eroded| eroded.len() * std::mem::size_of::<f32>())
+    }
+
+    /// The eroded value of the tile at the local position, see [ChunkGeneration::with_eroded].
+    pub(crate) fn eroded(&self, position: Vector2<usize>) -> Option<f32> {
+        let index = position.y * self.size.x + position.x;
+        self.eroded.as_ref().map(|eroded| eroded[index])
+    }
+
+    /// Get the tile at the local position, together with its parameters if they were retained.
+    pub fn get(&self, position: Vector2<usize>) -> (&Tile<T>, Option<ParameterValues<'_>>) {
+        let index = position.y * self.size.x + position.x;
+        (
+            &self.generation[index],
+            self.parameters.as_ref().map(|parameters| {
+                let start = index * self.parameter_count;
+                ParameterValues(&parameters[start..start + self.parameter_count])
+            }),
+        )
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (Vector2<usize>, &Tile<T>)> {
+        let width = self.size.x;
         self.generation
             .iter()
             .enumerate()
-            .map(|(index, gen)| (Vector2::new(index % W, index / W), gen))
+            .map(move |(index, gen)| (Vector2::new(index % width, index / width), gen))
+    }
+
+    /// Same as [ChunkGeneration::iter], but also yields the parameter values
+    /// of the tiles if they were retained during the generation.
+    pub fn iter_with_parameters(
+        &self,
+    ) -> impl Iterator<Item = (Vector2<usize>, &Tile<T>, Option<ParameterValues<'_>>)> {
+        self.iter().map(|(position, _)| {
+            let (gen, parameters) = self.get(position);
+            (position, gen, parameters)
+        })
+    }
+}
+
+/// The fields of a chunk in the order they are encoded.
+type EncodedChunk<T> = (
+    Vector2<usize>,
+    Vec<Tile<T>>,
+    Option<Vec<f32>>,
+    usize,
+    Option<Vec<f32>>,
+);
+
+impl<T: Serialize> ChunkGeneration<T> {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        bincode::serialize(&(
+            self.size,
+            &self.generation,
+            &self.parameters,
+            self.parameter_count,
+            &self.eroded,
+        ))
+        .expect("Failed to serialize a chunk")
+    }
+}
+
+impl<T: DeserializeOwned> ChunkGeneration<T> {
+    /// Decode a chunk encoded with [ChunkGeneration::encode].
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let (size, generation, parameters, parameter_count, eroded): EncodedChunk<T> =
+            bincode::deserialize(bytes).map_err(|error| error.to_string())?;
+        let tiles = size.x * size.y;
+        if generation.len() != tiles
+            || parameters
+                .as_ref()
+                .is_some_and(|parameters| parameters.len() != tiles * parameter_count)
+            || eroded.as_ref().is_some_and(|eroded| eroded.len() != tiles)
+        {
+            return Err("The chunk size does not match".to_owned());
+        }
+        let chunk = Self::new(size, generation, parameters, parameter_count);
+        Ok(match eroded {
+            Some(eroded) => chunk.with_eroded(eroded),
+            None => chunk,
+        })
     }
 }
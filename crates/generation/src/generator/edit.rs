@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::RangeInclusive;
+
+use super::*;
+
+/// Size of the cells of the world, by which the edits are looked up, in world units.
+const EDIT_CELL_SIZE: f32 = 64.0;
+
+/// The edited tiles of a chunk by their local positions.
+pub(crate) type ChunkEdits<T> = HashMap<LocalPos, Tile<T>>;
+
+/// A tile replaced with [WorldGenerator::set_tile], anchored to the world area
+/// that the tile covered, so that it stays in place when the scale changes.
+struct TileEdit<T> {
+    area: Area<f32>,
+    tile: Tile<T>,
+}
+
+/// The edits of a [WorldGenerator] by the order they were made in,
+/// where the edits overlap the later ones are applied.
+pub(crate) struct TileEdits<T> {
+    edits: BTreeMap<u64, TileEdit<T>>,
+    /// The edits overlapping each cell of the world, see [EDIT_CELL_SIZE].
+    cells: HashMap<Vector2<i64>, Vec<u64>>,
+    next_order: u64,
+}
+
+impl<T> TileEdits<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            edits: BTreeMap::new(),
+            cells: HashMap::new(),
+            next_order: 0,
+        }
+    }
+
+    /// Replace the tiles in the area, an earlier edit of exactly the same area is removed.
+    fn insert(&mut self, area: Area<f32>, tile: Tile<T>) {
+        let same = self
+            .overlapping(area)
+            .find(|(_, edit)| edit.area == area)
+            .map(|(order, _)| order);
+        if let Some(order) = same {
+            self.remove(order);
+        }
+
+        let order = self.next_order;
+        self.next_order += 1;
+        for cell in cells(area) {
+            self.cells.entry(cell).or_default().push(order);
+        }
+        self.edits.insert(order, TileEdit { area, tile });
+    }
+
+    fn remove(&mut self, order: u64) -> Option<TileEdit<T>> {
+        let edit = self.edits.remove(&order)?;
+        for cell in cells(edit.area) {
+            if let Some(orders) = self.cells.get_mut(&cell) {
+                orders.retain(|&other| other != order);
+                if orders.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(edit)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// The edits overlapping the area from the earliest to the latest.
+    fn overlapping(&self, area: Area<f32>) -> impl Iterator<Item = (u64, &TileEdit<T>)> {
+        let (xs, ys) = cell_ranges(area);
+        let area_cells = range_len(&xs).saturating_mul(range_len(&ys));
+        let orders: BTreeSet<u64> = if area_cells > self.cells.len() as u64 {
+            // A big area (e.g. a chunk of a coarse level of detail) covers fewer edited cells
+            // than there are cells in it, so only the edited ones are checked
+            self.cells
+                .iter()
+                .filter(|(cell, _)| xs.contains(&cell.x) && ys.contains(&cell.y))
+                .flat_map(|(_, orders)| orders)
+                .copied()
+                .collect()
+        } else {
+            cells(area)
+                .filter_map(|cell| self.cells.get(&cell))
+                .flatten()
+                .copied()
+                .collect()
+        };
+        orders
+            .into_iter()
+            .map(|order| (order, &self.edits[&order]))
+            .filter(move |(_, edit)| overlaps(edit.area, area))
+    }
+
+    /// The edit of the tile covering the area, sampled the same way the tiles are generated:
+    /// every sample point takes the latest edit containing it, and the tile takes
+    /// the most common one, unless most of the points are not edited.
+    fn tile(&self, area: Area<f32>, supersampling: Supersampling) -> Option<&Tile<T>> {
+        let edits: Vec<_> = self.overlapping(area).collect();
+        sample_edits(&edits, area, supersampling)
+    }
+}
+
+/// See [TileEdits::tile], `edits` are ordered from the earliest to the latest.
+fn sample_edits<'a, T>(
+    edits: &[(u64, &'a TileEdit<T>)],
+    area: Area<f32>,
+    supersampling: Supersampling,
+) -> Option<&'a Tile<T>> {
+    if edits.is_empty() {
+        return None;
+    }
+    let samples = supersampling.samples();
+    let step = (area.end - area.start) / samples as f32;
+    // Voting by the order of the edits, the points that are not edited vote for `None`
+    let mut votes = Votes::new();
+    for y in 0..samples {
+        for x in 0..samples {
+            let position = area.start + Vector2::new(x, y).map(|x| x as f32) * step;
+            let edit = edits
+                .iter()
+                .rev()
+                .find(|(_, edit)| contains(edit.area, position));
+            votes.add(
+                edit.map(|&(order, _)| order),
+                edit.map(|(_, edit)| &edit.tile),
+            );
+        }
+    }
+    votes.winner().flatten()
+}
+
+/// The ranges of the cells of the world overlapping the area, see [EDIT_CELL_SIZE].
+fn cell_ranges(area: Area<f32>) -> (RangeInclusive<i64>, RangeInclusive<i64>) {
+    let start = TilePos::from_world(area.start, Vector2::new(EDIT_CELL_SIZE, EDIT_CELL_SIZE));
+    let end = TilePos::from_world(area.end, Vector2::new(EDIT_CELL_SIZE, EDIT_CELL_SIZE));
+    (start.x..=end.x, start.y..=end.y)
+}
+
+fn range_len(range: &RangeInclusive<i64>) -> u64 {
+    range.end().abs_diff(*range.start()) + 1
+}
+
+/// The cells of the world overlapping the area, see [EDIT_CELL_SIZE].
+fn cells(area: Area<f32>) -> impl Iterator<Item = Vector2<i64>> {
+    let (xs, ys) = cell_ranges(area);
+    ys.flat_map(move |y| xs.clone().map(move |x| Vector2::new(x, y)))
+}
+
+/// Whether the areas overlap, the ends of the areas are exclusive.
+fn overlaps(a: Area<f32>, b: Area<f32>) -> bool {
+    a.start.x < b.end.x && b.start.x < a.end.x && a.start.y < b.end.y && b.start.y < a.end.y
+}
+
+fn contains(area: Area<f32>, position: Vector2<f32>) -> bool {
+    area.start.x <= position.x
+        && position.x < area.end.x
+        && area.start.y <= position.y
+        && position.y < area.end.y
+}
+
+impl<T> WorldGenerator<T> {
+    /// Replace the tile at the integer tile position of the current scale.
+    ///
+    /// The edits are stored separately from the generated chunks,
+    /// so they are kept when the chunks are evicted or generated again.
+    /// The edit is anchored to the world area of the tile, so it stays in place
+    /// when the scale changes: with every scale (and level of detail) a tile is edited
+    /// if its sample points are edited, the same way the tiles are generated
+    /// (see [Supersampling]). Where the edits overlap, the latest one is applied.
+    pub fn set_tile(&mut self, tile: TilePos, value: Option<TileValue<T>>) {
+        let area = tile.world_area(self.tile_size);
+        self.edits.insert(area, value);
+        self.emit_modified(&[area]);
+    }
+
+    /// Get the edit applied to the tile at the position of the current scale, if there is one.
+    pub fn edited_tile(&self, tile: TilePos) -> Option<&Option<TileValue<T>>> {
+        self.edits
+            .tile(tile.world_area(self.tile_size), self.supersampling)
+    }
+
+    /// Remove the edits overlapping the tile at the position of the current scale,
+    /// including the bigger ones made with coarser scales, so that it is generated again.
+    /// Returns the removed edits with their world areas.
+    pub fn revert_tile(&mut self, tile: TilePos) -> Vec<(Area<f32>, Option<TileValue<T>>)> {
+        let orders: Vec<u64> = self
+            .edits
+            .overlapping(tile.world_area(self.tile_size))
+            .map(|(order, _)| order)
+            .collect();
+        let removed: Vec<_> = orders
+            .into_iter()
+            .filter_map(|order| self.edits.remove(order))
+            .map(|edit| (edit.area, edit.tile))
+            .collect();
+        let areas: Vec<_> = removed.iter().map(|&(area, _)| area).collect();
+        self.emit_modified(&areas);
+        removed
+    }
+
+    /// Remove all edits.
+    pub fn revert_all_tiles(&mut self) {
+        let edits = std::mem::replace(&mut self.edits, TileEdits::new());
+        let areas: Vec<_> = edits.edits.values().map(|edit| edit.area).collect();
+        self.emit_modified(&areas);
+    }
+
+    /// Iterate over all edits with the world areas of the edited tiles,
+    /// from the earliest to the latest.
+    pub fn edits(&self) -> impl Iterator<Item = (Area<f32>, &Option<TileValue<T>>)> {
+        self.edits
+            .edits
+            .values()
+            .map(|edit| (edit.area, &edit.tile))
+    }
+
+    /// Send [ChunkEventKind::Modified] for the chunks of the current scale containing
+    /// the edited areas, and for the generated chunks of the other scales overlapping them.
+    fn emit_modified(&mut self, areas: &[Area<f32>]) {
+        let scale = self.scale();
+        let mut modified = HashSet::new();
+        for &area in areas {
+            let tile_size = self.tile_size;
+            let (start, end) = self.tile_area_chunks(Area {
+                start: TilePos::from_world(area.start, tile_size),
+                end: Vector2::new(
+                    (area.end.x / tile_size.x).ceil() as i64,
+                    (area.end.y / tile_size.y).ceil() as i64,
+                ),
+            });
+            for y in start.y..=end.y {
+                for x in start.x..=end.x {
+                    modified.insert((scale, Vector2::new(x, y)));
+                }
+            }
+            let chunk_size = self.chunk_size;
+            modified.extend(self.chunks.keys().filter(|&(scale, chunk_pos)| {
+                overlaps(chunk_pos.world_area(chunk_size, scale.tile_size()), area)
+            }));
+        }
+        for key in modified {
+            self.emit(ChunkEventKind::Modified, key);
+        }
+    }
+}
+
+impl<T: Copy> WorldGenerator<T> {
+    /// Get the tile at the integer tile position, taking the edits into account.
+    /// Uses the generated chunks when possible, otherwise samples the tile directly.
+    pub fn get_tile(&self, tile: TilePos) -> Option<TileValue<T>> {
+        self.sample_tile(tile).tile
+    }
+
+    /// The edited tiles of the chunk of the scale, see [WorldGenerator::set_tile].
+    pub(crate) fn chunk_edits(&self, scale: Scale, chunk_pos: ChunkPos) -> Option<ChunkEdits<T>> {
+        if self.edits.is_empty() {
+            return None;
+        }
+        let tile_size = scale.tile_size();
+        let edits: Vec<_> = self
+            .edits
+            .overlapping(chunk_pos.world_area(self.chunk_size, tile_size))
+            .collect();
+        if edits.is_empty() {
+            return None;
+        }
+
+        let tiles = chunk_pos.tiles(self.chunk_size);
+        let mut chunk_edits = ChunkEdits::new();
+        let mut sampled = HashSet::new();
+        for (_, edit) in &edits {
+            // The tiles of the chunk overlapping the edit
+            let start = TilePos::from_world(edit.area.start, tile_size);
+            let end = TilePos::from_world(edit.area.end, tile_size);
+            for y in start.y.max(tiles.start.y)..=end.y.min(tiles.end.y - 1) {
+                for x in start.x.max(tiles.start.x)..=end.x.min(tiles.end.x - 1) {
+                    let tile = Vector2::new(x, y);
+                    let local = (tile - tiles.start).map(|x| x as usize);
+                    if !sampled.insert(local) {
+                        continue;
+                    }
+                    let area = tile.world_area(tile_size);
+                    if let Some(&edited) = self.edits.tile(area, scale.supersampling()) {
+                        chunk_edits.insert(local, edited);
+                    }
+                }
+            }
+        }
+        Some(chunk_edits).filter(|edits| !edits.is_empty())
+    }
+}
@@ -0,0 +1,350 @@
+use super::*;
+
+/// Describes the erosion stage that is applied to a single parameter
+/// (usually the height) before the tiles are classified.
+///
+/// Erosion is simulated on the tile grid. To make neighbouring chunks agree
+/// on their borders, every chunk erodes a region that is bigger than the chunk
+/// by `padding` tiles on each side, and droplets are placed deterministically
+/// in world space, so the overlapping parts of two regions see the same droplets.
+///
+/// The agreement is only approximate: a droplet that leaves one region can still
+/// erode the other. On average the heights do not change more across the borders
+/// of the chunks than inside of them, but single tiles differ from the tiles
+/// eroded as a part of a bigger region (`tests/erosion.rs` checks both).
+/// For the same reason the eroded values depend on the chunk size
+/// (see [WorldGenerator::set_chunk_size]), so the same generator
+/// with different chunk sizes generates slightly different worlds.
+///
+/// Erosion is expensive: every chunk samples the noise on the whole padded region,
+/// e.g. with the default `padding` a chunk of 50x50 tiles samples 130x130 tiles,
+/// about 6.8 times more than without erosion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErosionProperties {
+    /// Seed used to place the droplets.
+    pub seed: u32,
+    /// Number of extra tiles eroded around each chunk.
+    /// Must be at least `droplet_lifetime + thermal_iterations` to hide the seams.
+    pub padding: usize,
+    /// Probability for a droplet to start at any given tile.
+    pub droplet_density: f32,
+    /// Maximum number of steps a droplet makes.
+    pub droplet_lifetime: usize,
+    /// How much a droplet keeps its direction instead of following the slope (0..=1).
+    pub inertia: f32,
+    /// How much sediment a droplet can carry, relative to its speed and water.
+    pub sediment_capacity: f32,
+    /// Minimum sediment capacity, so that droplets erode flat terrain a bit too.
+    pub min_sediment_capacity: f32,
+    /// Fraction of the free capacity that is eroded each step (0..=1).
+    pub erode_speed: f32,
+    /// Fraction of the excess sediment that is deposited each step (0..=1).
+    pub deposit_speed: f32,
+    /// Fraction of water that evaporates each step (0..=1).
+    pub evaporate_speed: f32,
+    pub gravity: f32,
+    /// Number of thermal erosion passes applied after the droplets.
+    pub thermal_iterations: usize,
+    /// Maximum stable height difference between two neighbouring tiles.
+    pub talus: f32,
+    /// Fraction of the unstable material moved each thermal pass (0..=1).
+    pub thermal_rate: f32,
+}
+
+impl Default for ErosionProperties {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            padding: 40,
+            droplet_density: 0.25,
+            droplet_lifetime: 30,
+            inertia: 0.05,
+            sediment_capacity: 4.0,
+            min_sediment_capacity: 0.01,
+            erode_speed: 0.3,
+            deposit_speed: 0.3,
+            evaporate_speed: 0.01,
+            gravity: 4.0,
+            thermal_iterations: 5,
+            talus: 0.5,
+            thermal_rate: 0.5,
+        }
+    }
+}
+
+impl ErosionProperties {
+    /// Hash the values of the properties, see [Generator::fingerprint].
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        // Destructured, so that the new fields are not forgotten
+        let Self {
+            seed,
+            padding,
+            droplet_density,
+            droplet_lifetime,
+            inertia,
+            sediment_capacity,
+            min_sediment_capacity,
+            erode_speed,
+            deposit_speed,
+            evaporate_speed,
+            gravity,
+            thermal_iterations,
+            talus,
+            thermal_rate,
+        } = *self;
+        hasher.write_u32(seed);
+        hasher.write_usize(padding);
+        hasher.write_f32(droplet_density);
+        hasher.write_usize(droplet_lifetime);
+        hasher.write_f32(inertia);
+        hasher.write_f32(sediment_capacity);
+        hasher.write_f32(min_sediment_capacity);
+        hasher.write_f32(erode_speed);
+        hasher.write_f32(deposit_speed);
+        hasher.write_f32(evaporate_speed);
+        hasher.write_f32(gravity);
+        hasher.write_usize(thermal_iterations);
+        hasher.write_f32(talus);
+        hasher.write_f32(thermal_rate);
+    }
+
+    /// All problems of the properties, e.g. rates out of their ranges
+    /// or the `padding` too small for the droplets to stay inside of it.
+    pub fn problems(&self) -> Vec<GenerationError> {
+        let mut problems = Vec::new();
+        let fractions = [
+            ("droplet_density", self.droplet_density),
+            ("inertia", self.inertia),
+            ("erode_speed", self.erode_speed),
+            ("deposit_speed", self.deposit_speed),
+            ("evaporate_speed", self.evaporate_speed),
+            ("thermal_rate", self.thermal_rate),
+        ];
+        for (property, value) in fractions {
+            if !(0.0..=1.0).contains(&value) {
+                problems.push(GenerationError::InvalidErosionProperty {
+                    property,
+                    value,
+                    expected: "from 0 to 1",
+                });
+            }
+        }
+        let amounts = [
+            ("sediment_capacity", self.sediment_capacity),
+            ("min_sediment_capacity", self.min_sediment_capacity),
+            ("gravity", self.gravity),
+            ("talus", self.talus),
+        ];
+        for (property, value) in amounts {
+            if !value.is_finite() || value < 0.0 {
+                problems.push(GenerationError::InvalidErosionProperty {
+                    property,
+                    value,
+                    expected: "finite and not negative",
+                });
+            }
+        }
+        let required = self
+            .droplet_lifetime
+            .saturating_add(self.thermal_iterations);
+        if self.padding < required {
+            problems.push(GenerationError::ErosionPaddingTooSmall {
+                padding: self.padding,
+                required,
+            });
+        }
+        problems
+    }
+
+    /// Erode the area of `size` tiles starting at the tile `start`.
+    /// `sample` should return the uneroded value at the given tile.
+    /// Returns the eroded values of the area in row-major order.
+    pub(crate) fn erode(
+        &self,
+        start: TilePos,
+        size: Vector2<usize>,
+        sample: impl Fn(TilePos) -> f32,
+    ) -> Vec<f32> {
+        let padding = self.padding as i64;
+        let region_start = Vector2::new(start.x - padding, start.y - padding);
+        let region_size = size.map(|x| x + self.padding * 2);
+
+        let mut values = Vec::with_capacity(region_size.x * region_size.y);
+        for y in 0..region_size.y {
+            for x in 0..region_size.x {
+                values.push(sample(Vector2::new(
+                    region_start.x + x as i64,
+                    region_start.y + y as i64,
+                )));
+            }
+        }
+        let mut field = HeightField {
+            size: region_size,
+            values,
+        };
+
+        for y in 0..region_size.y {
+            for x in 0..region_size.x {
+                let tile = Vector2::new(region_start.x + x as i64, region_start.y + y as i64);
+                if unit_hash(self.seed, tile, 0) < self.droplet_density {
+                    let offset =
+                        Vector2::new(unit_hash(self.seed, tile, 1), unit_hash(self.seed, tile, 2));
+                    let position = Vector2::new(x as f32 + offset.x, y as f32 + offset.y);
+                    self.simulate_droplet(&mut field, position);
+                }
+            }
+        }
+
+        for _ in 0..self.thermal_iterations {
+            self.thermal_pass(&mut field);
+        }
+
+        let mut eroded = Vec::with_capacity(size.x * size.y);
+        for y in 0..size.y {
+            let row = (y + self.padding) * region_size.x + self.padding;
+            eroded.extend_from_slice(&field.values[row..row + size.x]);
+        }
+        eroded
+    }
+
+    fn simulate_droplet(&self, field: &mut HeightField, mut position: Vector2<f32>) {
+        let mut direction = Vector2::new(0.0, 0.0);
+        let mut speed = 1.0;
+        let mut water = 1.0;
+        let mut sediment = 0.0;
+
+        for _ in 0..self.droplet_lifetime {
+            if !field.contains(position) {
+                return;
+            }
+            let (height, gradient) = field.height_and_gradient(position);
+
+            direction = direction * self.inertia - gradient * (1.0 - self.inertia);
+            let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+            if length <= f32::EPSILON {
+                // Flat terrain, the droplet stops here
+                return;
+            }
+            direction = direction / length;
+            let old_position = position;
+            position = position + direction;
+
+            if !field.contains(position) {
+                return;
+            }
+            let delta_height = field.height_and_gradient(position).0 - height;
+
+            let capacity = (-delta_height * speed * water * self.sediment_capacity)
+                .max(self.min_sediment_capacity);
+            if sediment > capacity || delta_height > 0.0 {
+                // Fill the pit we came into, or drop the excess sediment
+                let deposit = if delta_height > 0.0 {
+                    delta_height.min(sediment)
+                } else {
+                    (sediment - capacity) * self.deposit_speed
+                };
+                sediment -= deposit;
+                field.add(old_position, deposit);
+            } else {
+                // Never dig deeper than the next step, otherwise we create pits
+                let erode = ((capacity - sediment) * self.erode_speed).min(-delta_height);
+                sediment += erode;
+                field.add(old_position, -erode);
+            }
+
+            speed = (speed * speed - delta_height * self.gravity)
+                .max(0.0)
+                .sqrt();
+            water *= 1.0 - self.evaporate_speed;
+        }
+    }
+
+    fn thermal_pass(&self, field: &mut HeightField) {
+        let size = field.size;
+        let mut delta = vec![0.0; field.values.len()];
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = y * size.x + x;
+                let height = field.values[index];
+                let neighbours = [
+                    (x + 1 < size.x).then(|| index + 1),
+                    (y + 1 < size.y).then(|| index + size.x),
+                ];
+                for neighbour in neighbours.into_iter().flatten() {
+                    let difference = height - field.values[neighbour];
+                    if difference.abs() > self.talus {
+                        let moved = (difference.abs() - self.talus) * self.thermal_rate / 2.0
+                            * difference.signum();
+                        delta[index] -= moved;
+                        delta[neighbour] += moved;
+                    }
+                }
+            }
+        }
+        for (value, delta) in field.values.iter_mut().zip(delta) {
+            *value += delta;
+        }
+    }
+}
+
+struct HeightField {
+    size: Vector2<usize>,
+    values: Vec<f32>,
+}
+
+impl HeightField {
+    /// Checks that the position can be interpolated.
+    fn contains(&self, position: Vector2<f32>) -> bool {
+        position.x >= 0.0
+            && position.y >= 0.0
+            && position.x < (self.size.x - 1) as f32
+            && position.y < (self.size.y - 1) as f32
+    }
+
+    fn get(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.size.x + x]
+    }
+
+    /// Bilinear interpolation of the height and its gradient.
+    fn height_and_gradient(&self, position: Vector2<f32>) -> (f32, Vector2<f32>) {
+        let (x, y) = (position.x as usize, position.y as usize);
+        let (u, v) = (position.x - x as f32, position.y - y as f32);
+        let h00 = self.get(x, y);
+        let h10 = self.get(x + 1, y);
+        let h01 = self.get(x, y + 1);
+        let h11 = self.get(x + 1, y + 1);
+
+        let gradient = Vector2::new(
+            (h10 - h00) * (1.0 - v) + (h11 - h01) * v,
+            (h01 - h00) * (1.0 - u) + (h11 - h10) * u,
+        );
+        let height =
+            h00 * (1.0 - u) * (1.0 - v) + h10 * u * (1.0 - v) + h01 * (1.0 - u) * v + h11 * u * v;
+        (height, gradient)
+    }
+
+    /// Distributes the amount between the four surrounding tiles.
+    fn add(&mut self, position: Vector2<f32>, amount: f32) {
+        let (x, y) = (position.x as usize, position.y as usize);
+        let (u, v) = (position.x - x as f32, position.y - y as f32);
+        let width = self.size.x;
+        self.values[y * width + x] += amount * (1.0 - u) * (1.0 - v);
+        self.values[y * width + x + 1] += amount * u * (1.0 - v);
+        self.values[(y + 1) * width + x] += amount * (1.0 - u) * v;
+        self.values[(y + 1) * width + x + 1] += amount * u * v;
+    }
+}
+
+/// Deterministic hash of a tile into the range `0..1`.
+fn unit_hash(seed: u32, tile: TilePos, salt: u64) -> f32 {
+    let mut hash = (seed as u64) ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    for value in [tile.x as u64, tile.y as u64] {
+        hash ^= value;
+        hash = hash.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash ^= hash >> 31;
+        hash = hash.wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 29;
+    }
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
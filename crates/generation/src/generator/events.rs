@@ -0,0 +1,68 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use super::*;
+
+/// Something that happened to a chunk of a [WorldGenerator], see [WorldGenerator::subscribe].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkEvent {
+    pub kind: ChunkEventKind,
+    pub chunk_pos: ChunkPos,
+    /// The tile size of the chunk, the chunks of every scale are kept side by side.
+    pub tile_size: Vector2<f32>,
+    pub supersampling: Supersampling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkEventKind {
+    /// The chunk was generated, synchronously or in the background.
+    Generated,
+    /// The chunk was loaded from the storage instead of being generated, see [ChunkStorage].
+    Loaded,
+    /// The edits overlapping the chunk have changed, see [WorldGenerator::set_tile].
+    /// Sent for the chunks of the current scale, even if they are not generated,
+    /// and for the generated chunks of the other scales.
+    Modified,
+    /// The chunk is not kept anymore: it was evicted by the [CachePolicy],
+    /// unloaded, or cleared because the generator has changed.
+    Evicted,
+    /// Generating the chunk in the background panicked, see [WorldGenerator::request_area].
+    /// The chunk is not requested again until the generator changes,
+    /// or its request is cancelled, see [WorldGenerator::cancel_requests_outside].
+    Failed,
+}
+
+impl<T> WorldGenerator<T> {
+    /// Receive the events of the chunks from now on, e.g. drain the receiver
+    /// with [Receiver::try_iter] every frame. There can be any number of subscribers,
+    /// every one of them receives every event. Dropping the receiver unsubscribes.
+    ///
+    /// The events are queued without a limit, so that generating never waits for
+    /// the subscribers: a receiver that is kept but never drained grows for as long
+    /// as chunks are generated. Drop the receivers that are not needed anymore.
+    pub fn subscribe(&mut self) -> Receiver<ChunkEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Send the event to the subscribers, and forget the ones that are gone.
+    pub(crate) fn emit(&mut self, kind: ChunkEventKind, (scale, chunk_pos): ChunkKey) {
+        let event = ChunkEvent {
+            kind,
+            chunk_pos,
+            tile_size: scale.tile_size(),
+            supersampling: scale.supersampling(),
+        };
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event).is_ok());
+    }
+
+    pub(crate) fn emit_evicted(&mut self, keys: Vec<ChunkKey>) {
+        for key in keys {
+            self.emit(ChunkEventKind::Evicted, key);
+        }
+    }
+}
+
+/// The senders of the events to the subscribers.
+pub(crate) type Subscribers = Vec<Sender<ChunkEvent>>;
@@ -1,108 +1,828 @@
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
+
 use super::*;
 
 pub type GenerationParameter = String;
 
+/// Identifies a parameter (a noise) inside of a [Generator].
+/// The ids are dense, so the parameter values can be stored in a flat array.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ParameterId(usize);
+
+impl ParameterId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// The values of all parameters of a single tile, indexed by [ParameterId].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParameterValues<'a>(pub(crate) &'a [f32]);
+
+impl<'a> ParameterValues<'a> {
+    pub fn get(&self, id: ParameterId) -> Option<f32> {
+        self.0.get(id.0).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ParameterId, f32)> + 'a {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| (ParameterId(index), value))
+    }
+
+    pub fn as_slice(&self) -> &'a [f32] {
+        self.0
+    }
+}
+
+impl Index<ParameterId> for ParameterValues<'_> {
+    type Output = f32;
+
+    fn index(&self, id: ParameterId) -> &f32 {
+        &self.0[id.0]
+    }
+}
+
+/// Identifies a generation inside of a [Generator].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct GenerationId(usize);
+
+/// The generation and the sub-generation chosen for a tile,
+/// comparable even if the generation values are not.
+pub(crate) type GenerationKey = Option<(GenerationId, Option<GenerationId>)>;
+
+#[derive(Clone)]
+struct GenerationEntry<T> {
+    id: GenerationId,
+    /// The generation inside of which this one applies.
+    /// `None` for the top-level generations.
+    parent: Option<GenerationId>,
+    value: T,
+    /// The parameter ranges resolved to the parameter ids, sorted by the ids.
+    ranges: Vec<(ParameterId, ParameterRange)>,
+    /// Indices into `ranges` from the cheapest noise to the most expensive one.
+    check_order: Vec<usize>,
+}
+
+impl<T> GenerationEntry<T> {
+    fn sort_check_order(&mut self, noises: &[MultiNoise]) {
+        let ranges = &self.ranges;
+        self.check_order
+            .sort_by_key(|&index| noises[ranges[index].0.index()].cost());
+    }
+}
+
+#[derive(Clone)]
 pub struct Generator<T> {
-    noises: HashMap<GenerationParameter, MultiNoise>,
-    generations: Vec<(T, TileGeneration)>,
+    /// Noise names, indexed by [ParameterId].
+    parameters: Vec<GenerationParameter>,
+    /// Noises, indexed by [ParameterId].
+    noises: Vec<MultiNoise>,
+    generations: Vec<GenerationEntry<T>>,
+    next_generation_id: usize,
+    erosion: Option<(ParameterId, ErosionProperties)>,
+    /// The value of the tiles, that fit no generation.
+    fallback: Option<T>,
+    /// Increased on every change, so that the chunks generated before can be discarded.
+    revision: u64,
+    /// Increased on the changes of the generated tiles, the other changes
+    /// only affect the parameter values, see [Generator::tiles_revision].
+    tiles_revision: u64,
 }
 
 impl<T> Generator<T> {
     pub(crate) fn new() -> Self {
         Self {
-            noises: HashMap::new(),
+            parameters: vec![],
+            noises: vec![],
             generations: vec![],
+            next_generation_id: 0,
+            erosion: None,
+            fallback: None,
+            revision: 0,
+            tiles_revision: 0,
         }
     }
 
+    /// Increased on every change of the generator.
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Increased on every change of the generated tiles. The chunks generated
+    /// before other changes only have to be discarded if they retain the parameter values.
+    pub(crate) fn tiles_revision(&self) -> u64 {
+        self.tiles_revision
+    }
+
+    fn tiles_changed(&mut self) {
+        self.revision += 1;
+        self.tiles_revision += 1;
+    }
+
+    /// Add a noise and return the id of its parameter.
+    /// The names must be unique, use [Generator::set_noise] to replace a noise.
     pub fn add_noise(
         &mut self,
         name: &str,
         noise_seed: u32,
         noise_parameters: MultiNoiseProperties,
-    ) {
-        self.noises.insert(
-            name.to_owned(),
-            MultiNoise::new(noise_seed, noise_parameters),
-        );
+    ) -> Result<ParameterId, GenerationError> {
+        if self.parameter(name).is_some() {
+            return Err(GenerationError::DuplicateNoise {
+                name: name.to_owned(),
+            });
+        }
+        if let Some(problem) = noise_parameters.problems(name).into_iter().next() {
+            return Err(problem);
+        }
+
+        self.parameters.push(name.to_owned());
+        self.noises
+            .push(MultiNoise::new(noise_seed, noise_parameters));
+        // No generation uses the new noise yet
+        self.revision += 1;
+        Ok(ParameterId(self.noises.len() - 1))
+    }
+
+    /// Find the id of the parameter by its name.
+    pub fn parameter(&self, name: &str) -> Option<ParameterId> {
+        self.parameters
+            .iter()
+            .position(|parameter| parameter == name)
+            .map(ParameterId)
+    }
+
+    pub fn parameter_name(&self, id: ParameterId) -> &str {
+        &self.parameters[id.0]
+    }
+
+    /// Number of parameters, all parameter ids are less than that.
+    pub fn parameter_count(&self) -> usize {
+        self.parameters.len()
     }
 
     pub fn add_generation(
         &mut self,
         generation: T,
         gen_parameters: TileGeneration,
+    ) -> Result<GenerationId, GenerationError> {
+        self.push_generation(None, generation, gen_parameters)
+    }
+
+    /// Add a generation that only applies inside of the `parent` generation.
+    /// The sub-generations of the same parent compete with each other
+    /// the same way the top-level generations do.
+    pub fn add_sub_generation(
+        &mut self,
+        parent: GenerationId,
+        generation: T,
+        gen_parameters: TileGeneration,
+    ) -> Result<GenerationId, GenerationError> {
+        if !self.generations.iter().any(|entry| entry.id == parent) {
+            return Err(GenerationError::GenerationNotFound { id: parent });
+        }
+
+        self.push_generation(Some(parent), generation, gen_parameters)
+    }
+
+    fn push_generation(
+        &mut self,
+        parent: Option<GenerationId>,
+        generation: T,
+        gen_parameters: TileGeneration,
+    ) -> Result<GenerationId, GenerationError> {
+        let ranges = self.resolve_ranges(&gen_parameters)?;
+        let id = GenerationId(self.next_generation_id);
+        self.next_generation_id += 1;
+        let mut entry = GenerationEntry {
+            id,
+            parent,
+            value: generation,
+            check_order: (0..ranges.len()).collect(),
+            ranges,
+        };
+        entry.sort_check_order(&self.noises);
+        self.generations.push(entry);
+        self.tiles_changed();
+        Ok(id)
+    }
+
+    /// Erode the values of the parameter before classifying the tiles.
+    /// Replaces the previous erosion, if there was one.
+    /// Returns the first of [ErosionProperties::problems], if there are any.
+    pub fn set_erosion(
+        &mut self,
+        parameter: &str,
+        properties: ErosionProperties,
     ) -> Result<(), GenerationError> {
+        let id = self
+            .parameter(parameter)
+            .ok_or_else(|| GenerationError::NoiseNotFound {
+                name: parameter.to_owned(),
+            })?;
+        if let Some(problem) = properties.problems().into_iter().next() {
+            return Err(problem);
+        }
+
+        self.erosion = Some((id, properties));
+        self.tiles_changed();
+        Ok(())
+    }
+
+    pub fn disable_erosion(&mut self) {
+        self.erosion = None;
+        self.tiles_changed();
+    }
+
+    pub(crate) fn has_erosion(&self) -> bool {
+        self.erosion.is_some()
+    }
+
+    /// The value of the tiles, that fit no generation.
+    pub fn fallback(&self) -> Option<&T> {
+        self.fallback.as_ref()
+    }
+
+    /// Set the value of the tiles, that fit no generation.
+    /// Without a fallback such tiles are left empty (`None`).
+    pub fn set_fallback(&mut self, fallback: Option<T>) {
+        self.fallback = fallback;
+        self.tiles_changed();
+    }
+
+    /// The parameter ranges resolved to the parameter ids, sorted by the ids.
+    fn resolve_ranges(
+        &self,
+        gen_parameters: &TileGeneration,
+    ) -> Result<Vec<(ParameterId, ParameterRange)>, GenerationError> {
         let parameters = gen_parameters.parameter_values.len();
         let noises = self.noises.len();
         if parameters > noises {
             return Err(GenerationError::TooManyParameters { parameters, noises });
         }
 
-        for parameter in gen_parameters.parameter_values.keys() {
-            if !self.noises.contains_key(parameter) {
-                return Err(GenerationError::NoiseNotFound {
-                    name: parameter.to_owned(),
-                });
+        let mut ranges = gen_parameters
+            .parameter_values
+            .iter()
+            .map(|(parameter, &range)| {
+                let id =
+                    self.parameter(parameter)
+                        .ok_or_else(|| GenerationError::NoiseNotFound {
+                            name: parameter.to_owned(),
+                        })?;
+                range.check(parameter)?;
+                Ok((id, range))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ranges.sort_by_key(|&(parameter, _)| parameter);
+        Ok(ranges)
+    }
+
+    /// The noises in the order of their ids, with their names.
+    pub fn noises(&self) -> impl Iterator<Item = (ParameterId, &str, &MultiNoise)> {
+        self.parameters
+            .iter()
+            .zip(&self.noises)
+            .enumerate()
+            .map(|(index, (name, noise))| (ParameterId(index), name.as_str(), noise))
+    }
+
+    pub fn noise(&self, id: ParameterId) -> Option<&MultiNoise> {
+        self.noises.get(id.0)
+    }
+
+    /// Replace the noise of the parameter, keeping its name and id.
+    /// The generated tiles are only affected if the noise is used
+    /// by a generation or by the erosion.
+    pub fn set_noise(
+        &mut self,
+        id: ParameterId,
+        noise_seed: u32,
+        noise_parameters: MultiNoiseProperties,
+    ) -> Result<(), GenerationError> {
+        let name = self
+            .parameters
+            .get(id.0)
+            .ok_or(GenerationError::ParameterNotFound { id })?;
+        if let Some(problem) = noise_parameters.problems(name).into_iter().next() {
+            return Err(problem);
+        }
+
+        self.noises[id.0] = MultiNoise::new(noise_seed, noise_parameters);
+        // The cost of the noise might have changed
+        for entry in &mut self.generations {
+            entry.sort_check_order(&self.noises);
+        }
+        if self.noise_in_use(id) {
+            self.tiles_changed();
+        } else {
+            self.revision += 1;
+        }
+        Ok(())
+    }
+
+    /// Remove the noise. The noises used by a generation or by the erosion can not be removed.
+    /// The ids of the following parameters decrease by one.
+    pub fn remove_noise(&mut self, id: ParameterId) -> Result<(), GenerationError> {
+        let name = self
+            .parameters
+            .get(id.0)
+            .ok_or(GenerationError::ParameterNotFound { id })?;
+        if self.noise_in_use(id) {
+            return Err(GenerationError::NoiseInUse { name: name.clone() });
+        }
+
+        let mut order: Vec<usize> = (0..self.noises.len()).collect();
+        order.remove(id.0);
+        self.reorder_noises(&order);
+        Ok(())
+    }
+
+    /// Whether the noise is used by a generation or by the erosion.
+    fn noise_in_use(&self, id: ParameterId) -> bool {
+        let used_by_generation = self
+            .generations
+            .iter()
+            .any(|entry| entry.ranges.iter().any(|&(parameter, _)| parameter == id));
+        let used_by_erosion = matches!(self.erosion, Some((parameter, _)) if parameter == id);
+        used_by_generation || used_by_erosion
+    }
+
+    /// Move the noise to the position `index`, shifting the noises in between.
+    /// Changes the ids of the moved parameters, and so the order of the parameter values,
+    /// but the generated tiles stay the same.
+    pub fn move_noise(&mut self, id: ParameterId, index: usize) -> Result<(), GenerationError> {
+        let len = self.noises.len();
+        if id.0 >= len {
+            return Err(GenerationError::ParameterNotFound { id });
+        }
+        if index >= len {
+            return Err(GenerationError::IndexOutOfRange { index, len });
+        }
+        let mut order: Vec<usize> = (0..self.noises.len()).collect();
+        order.remove(id.0);
+        order.insert(index, id.0);
+        self.reorder_noises(&order);
+        Ok(())
+    }
+
+    /// Rearrange the noises, so that the new parameter `i` is the old parameter `order[i]`.
+    /// The old parameters missing from `order` must not be used.
+    fn reorder_noises(&mut self, order: &[usize]) {
+        let mut new_ids = vec![None; self.noises.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_ids[old] = Some(ParameterId(new));
+        }
+        let remap = |id: ParameterId| new_ids[id.0].expect("Removed parameter is used");
+
+        let mut parameters: Vec<_> = self.parameters.drain(..).map(Some).collect();
+        let mut noises: Vec<_> = self.noises.drain(..).map(Some).collect();
+        for &old in order {
+            self.parameters.push(parameters[old].take().unwrap());
+            self.noises.push(noises[old].take().unwrap());
+        }
+        for entry in &mut self.generations {
+            for (parameter, _) in &mut entry.ranges {
+                *parameter = remap(*parameter);
+            }
+            entry.ranges.sort_by_key(|&(parameter, _)| parameter);
+            entry.check_order = (0..entry.ranges.len()).collect();
+            entry.sort_check_order(&self.noises);
+        }
+        if let Some((parameter, _)) = &mut self.erosion {
+            *parameter = remap(*parameter);
+        }
+        // The noises are only checked in a different order
+        self.revision += 1;
+    }
+
+    /// Check the whole generator and return all of its problems at once,
+    /// e.g. to show them in an editor. The generator can not be changed
+    /// into an invalid state, so normally there are none.
+    pub fn validate(&self) -> Vec<GenerationError> {
+        let mut problems = Vec::new();
+        for (index, (name, noise)) in self.parameters.iter().zip(&self.noises).enumerate() {
+            if self.parameters[..index].contains(name) {
+                problems.push(GenerationError::DuplicateNoise { name: name.clone() });
+            }
+            problems.extend(noise.properties().problems(name));
+        }
+        if let Some((_, properties)) = &self.erosion {
+            problems.extend(properties.problems());
+        }
+        for entry in &self.generations {
+            for &(parameter, range) in &entry.ranges {
+                if let Err(error) = range.check(self.parameter_name(parameter)) {
+                    problems.push(error);
+                }
             }
         }
+        problems
+    }
 
-        self.generations.push((generation, gen_parameters));
+    /// The generations in the order they are checked, the sub-generations after their parents.
+    pub fn generations(&self) -> impl Iterator<Item = GenerationInfo<'_, T>> {
+        self.generations.iter().map(GenerationInfo::new)
+    }
+
+    pub fn generation(&self, id: GenerationId) -> Option<GenerationInfo<'_, T>> {
+        self.generations
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(GenerationInfo::new)
+    }
+
+    fn generation_index(&self, id: GenerationId) -> Result<usize, GenerationError> {
+        self.generations
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(GenerationError::GenerationNotFound { id })
+    }
+
+    /// Replace the value of the generation, returns the previous one.
+    pub fn set_generation_value(
+        &mut self,
+        id: GenerationId,
+        value: T,
+    ) -> Result<T, GenerationError> {
+        let index = self.generation_index(id)?;
+        self.tiles_changed();
+        Ok(std::mem::replace(&mut self.generations[index].value, value))
+    }
+
+    /// Replace the parameter ranges of the generation.
+    pub fn set_generation_parameters(
+        &mut self,
+        id: GenerationId,
+        gen_parameters: TileGeneration,
+    ) -> Result<(), GenerationError> {
+        let index = self.generation_index(id)?;
+        let ranges = self.resolve_ranges(&gen_parameters)?;
+        let entry = &mut self.generations[index];
+        entry.check_order = (0..ranges.len()).collect();
+        entry.ranges = ranges;
+        entry.sort_check_order(&self.noises);
+        self.tiles_changed();
         Ok(())
     }
+
+    /// Remove the generation together with all of its sub-generations.
+    pub fn remove_generation(&mut self, id: GenerationId) -> Result<(), GenerationError> {
+        self.generation_index(id)?;
+        let mut removed = vec![id];
+        // The sub-generations are always added after their parents
+        for entry in &self.generations {
+            if entry.parent.is_some_and(|parent| removed.contains(&parent)) {
+                removed.push(entry.id);
+            }
+        }
+        self.generations
+            .retain(|entry| !removed.contains(&entry.id));
+        self.tiles_changed();
+        Ok(())
+    }
+
+    /// Move the generation to the position `index` among all generations.
+    /// Affects which of the equally fitting generations is chosen: the first one wins.
+    /// A sub-generation can not be moved before its parent.
+    pub fn move_generation(
+        &mut self,
+        id: GenerationId,
+        index: usize,
+    ) -> Result<(), GenerationError> {
+        let old = self.generation_index(id)?;
+        let len = self.generations.len();
+        if index >= len {
+            return Err(GenerationError::IndexOutOfRange { index, len });
+        }
+        let entry = self.generations.remove(old);
+        self.generations.insert(index, entry);
+        let parent_after = |position: usize| {
+            let entry = &self.generations[position];
+            entry.parent.is_some_and(|parent| {
+                self.generations[position..]
+                    .iter()
+                    .any(|other| other.id == parent)
+            })
+        };
+        if (0..self.generations.len()).any(parent_after) {
+            let entry = self.generations.remove(index);
+            self.generations.insert(old, entry);
+            return Err(GenerationError::InvalidOrder { id });
+        }
+        self.tiles_changed();
+        Ok(())
+    }
+}
+
+/// Describes a generation of a [Generator], see [Generator::generations].
+#[derive(Debug)]
+pub struct GenerationInfo<'a, T> {
+    pub id: GenerationId,
+    /// `None` for the top-level generations.
+    pub parent: Option<GenerationId>,
+    pub value: &'a T,
+    /// The parameter ranges sorted by the parameter ids.
+    pub ranges: &'a [(ParameterId, ParameterRange)],
+}
+
+impl<'a, T> GenerationInfo<'a, T> {
+    fn new(entry: &'a GenerationEntry<T>) -> Self {
+        Self {
+            id: entry.id,
+            parent: entry.parent,
+            value: &entry.value,
+            ranges: &entry.ranges,
+        }
+    }
+}
+
+impl<T: Hash> Generator<T> {
+    /// A hash of everything that affects the generation: the noises with their seeds
+    /// and properties, the generations with their parameter ranges and the erosion,
+    /// as well as the [GENERATION_VERSION].
+    /// Stays the same between runs and platforms, as long as the generation values
+    /// hash the same way (e.g. `#[derive(Hash)]` on an enum).
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(GENERATION_VERSION);
+        hasher.write_usize(self.noises.len());
+        for (name, noise) in self.parameters.iter().zip(&self.noises) {
+            name.hash(hasher);
+            hasher.write_u32(noise.seed());
+            noise.properties().hash_into(hasher);
+        }
+
+        // The parents are identified by their positions, not by the ids,
+        // so that the same generations added in a different way hash the same
+        hasher.write_usize(self.generations.len());
+        for entry in &self.generations {
+            let parent = entry
+                .parent
+                .and_then(|parent| self.generations.iter().position(|other| other.id == parent));
+            match parent {
+                Some(parent) => hasher.write_usize(parent + 1),
+                None => hasher.write_usize(0),
+            }
+            entry.value.hash(hasher);
+            hasher.write_usize(entry.ranges.len());
+            for &(parameter, range) in &entry.ranges {
+                hasher.write_usize(parameter.0);
+                hasher.write_f32(range.min);
+                hasher.write_f32(range.max);
+            }
+        }
+
+        match &self.erosion {
+            Some((parameter, properties)) => {
+                hasher.write_u8(1);
+                hasher.write_usize(parameter.0);
+                properties.hash_into(hasher);
+            }
+            None => hasher.write_u8(0),
+        }
+
+        match &self.fallback {
+            Some(fallback) => {
+                hasher.write_u8(1);
+                fallback.hash(hasher);
+            }
+            None => hasher.write_u8(0),
+        }
+    }
 }
 
 impl<T: Copy> Generator<T> {
     pub(crate) fn generate_chunk(
         &self,
-        chunk_pos: Vector2<i32>,
+        chunk_pos: ChunkPos,
+        chunk_size: Vector2<usize>,
         tile_size: Vector2<f32>,
-    ) -> ChunkGeneration<T, CHUNK_WIDTH, CHUNK_HEIGHT> {
-        let chunk_size = CHUNK_SIZE.map(|x| x as f32);
-        let tile_start = chunk_pos.map(|x| x as f32) * chunk_size * tile_size;
-
-        let mut generation = Vec::with_capacity(CHUNK_WIDTH * CHUNK_HEIGHT);
-        for y in 0..CHUNK_HEIGHT {
-            for x in 0..CHUNK_WIDTH {
-                let position = Vector2::new(x, y).map(|x| x as f32);
-                let position = tile_start + position * tile_size;
-                let gen = self.generate(position);
+        supersampling: Supersampling,
+        retain_parameters: bool,
+    ) -> ChunkGeneration<T> {
+        let eroded = self.erosion.as_ref().map(|&(parameter, ref erosion)| {
+            let noise = &self.noises[parameter.0];
+            let start = Vector2::new(
+                chunk_pos.x as i64 * chunk_size.x as i64,
+                chunk_pos.y as i64 * chunk_size.y as i64,
+            );
+            let values = erosion.erode(start, chunk_size, |tile| {
+                noise.get(tile.map(|x| x as f32) * tile_size)
+            });
+            (parameter, values)
+        });
+
+        let parameter_count = self.parameter_count();
+        let mut generation = Vec::with_capacity(chunk_size.x * chunk_size.y);
+        let mut parameters = retain_parameters
+            .then(|| Vec::with_capacity(chunk_size.x * chunk_size.y * parameter_count));
+        let mut noise_values = LazyParameters::new(&self.noises);
+        for y in 0..chunk_size.y {
+            for x in 0..chunk_size.x {
+                // The same area as a single sampled tile, see [WorldGenerator::sample]
+                let area = TilePos::from_chunk(chunk_pos, Vector2::new(x, y), chunk_size)
+                    .world_area(tile_size);
+                let eroded = eroded
+                    .as_ref()
+                    .map(|(parameter, values)| (*parameter, values[y * chunk_size.x + x]));
+                let gen = self.generate_tile(
+                    &mut noise_values,
+                    area,
+                    eroded,
+                    supersampling,
+                    retain_parameters,
+                );
                 generation.push(gen);
+                if let Some(parameters) = &mut parameters {
+                    parameters.extend_from_slice(noise_values.evaluate_all());
+                }
             }
         }
 
-        ChunkGeneration::new(generation)
+        let chunk = ChunkGeneration::new(chunk_size, generation, parameters, parameter_count);
+        match eroded {
+            Some((_, values)) => chunk.with_eroded(values),
+            None => chunk,
+        }
     }
 
-    fn generate(&self, position: Vector2<f32>) -> Option<T> {
-        let noise_values: HashMap<GenerationParameter, f32> = self
-            .noises
-            .iter()
-            .map(|(parameter, noise)| (parameter.to_owned(), noise.get(position)))
-            .collect();
+    /// Generate the tile covering the world area together with all of its parameter values.
+    /// `eroded` is the eroded value of the tile, without it erosion is ignored.
+    pub(crate) fn generate_with_parameters(
+        &self,
+        area: Area<f32>,
+        eroded: Option<f32>,
+        supersampling: Supersampling,
+    ) -> (Tile<T>, Vec<f32>) {
+        let eroded = eroded.and_then(|value| {
+            self.erosion
+                .as_ref()
+                .map(|&(parameter, _)| (parameter, value))
+        });
+        let mut noise_values = LazyParameters::new(&self.noises);
+        let gen = self.generate_tile(&mut noise_values, area, eroded, supersampling, true);
+        (gen, noise_values.evaluate_all().to_vec())
+    }
+
+    /// Choose the generation that fits the parameter values best,
+    /// and then its most fitting sub-generations.
+    /// Noises are only sampled when some candidate needs them.
+    pub(crate) fn generate(&self, noise_values: &mut LazyParameters) -> Tile<T> {
+        self.generate_keyed(noise_values).0
+    }
+
+    /// Same as [Generator::generate], but also returns the ids of the chosen generations.
+    pub(crate) fn generate_keyed(
+        &self,
+        noise_values: &mut LazyParameters,
+    ) -> (Tile<T>, GenerationKey) {
+        let parent = match self.best_generation(None, noise_values) {
+            Some(parent) => parent,
+            None => {
+                let fallback = self.fallback.map(|parent| TileValue {
+                    parent,
+                    child: None,
+                });
+                return (fallback, None);
+            }
+        };
+        let mut child = None;
+        let mut current = parent;
+        while let Some(sub) = self.best_generation(Some(current.id), noise_values) {
+            child = Some(sub);
+            current = sub;
+        }
+        let tile = TileValue {
+            parent: parent.value,
+            child: child.map(|child| child.value),
+        };
+        (Some(tile), Some((parent.id, child.map(|child| child.id))))
+    }
 
+    fn best_generation(
+        &self,
+        parent: Option<GenerationId>,
+        noise_values: &mut LazyParameters,
+    ) -> Option<&GenerationEntry<T>> {
         self.generations
             .iter()
-            .filter_map(|(gen, generation)| {
-                let mut total_score = 0.0;
-                for (parameter, range) in &generation.parameter_values {
-                    let value = noise_values[parameter];
-                    let score = (value - range.min).min(range.max - value);
-                    if score < 0.0 {
-                        return None;
-                    }
-                    total_score += score;
+            .filter(|entry| entry.parent == parent)
+            .filter_map(|entry| {
+                if !Self::fits(entry, noise_values) {
+                    return None;
                 }
-                Some((gen, total_score))
+                let total_score: f32 = entry
+                    .ranges
+                    .iter()
+                    .map(|&(parameter, range)| {
+                        let value = noise_values.get(parameter);
+                        (value - range.min).min(range.max - value)
+                    })
+                    .sum();
+                Some((entry, total_score))
             })
             .min_by(|(_, score1), (_, score2)| score1.partial_cmp(score2).unwrap())
-            .map(|(gen, _)| *gen)
+            .map(|(entry, _)| entry)
+    }
+
+    /// Check that all parameter values are inside of the ranges.
+    /// The values that are already known are checked first, since they are free,
+    /// and then the rest from the cheapest to the most expensive one,
+    /// so that the expensive noises are only sampled if they are needed.
+    fn fits(entry: &GenerationEntry<T>, noise_values: &mut LazyParameters) -> bool {
+        for evaluated in [true, false] {
+            for &index in &entry.check_order {
+                let (parameter, range) = entry.ranges[index];
+                if noise_values.is_evaluated(parameter) != evaluated {
+                    continue;
+                }
+                let value = noise_values.get(parameter);
+                if value < range.min || value > range.max {
+                    return false;
+                }
+            }
+        }
+        true
     }
 }
 
 #[derive(Debug)]
 pub enum GenerationError {
-    TooManyParameters { parameters: usize, noises: usize },
-    NoiseNotFound { name: GenerationParameter },
+    TooManyParameters {
+        parameters: usize,
+        noises: usize,
+    },
+    NoiseNotFound {
+        name: GenerationParameter,
+    },
+    GenerationNotFound {
+        id: GenerationId,
+    },
+    ParameterNotFound {
+        id: ParameterId,
+    },
+    NoiseInUse {
+        name: GenerationParameter,
+    },
+    InvalidOrder {
+        id: GenerationId,
+    },
+    /// The position to move a noise or a generation to is past the last one.
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    DuplicateNoise {
+        name: GenerationParameter,
+    },
+    /// `min_value` of the noise is greater than `max_value`, or one of them is not finite.
+    InvalidNoiseRange {
+        name: GenerationParameter,
+        min_value: f32,
+        max_value: f32,
+    },
+    /// The scale of the noise is not positive or not finite.
+    InvalidNoiseScale {
+        name: GenerationParameter,
+        scale: f32,
+    },
+    ZeroOctaves {
+        name: GenerationParameter,
+    },
+    /// `lacunarity` or `persistance` of the noise is not finite.
+    NonFiniteNoiseProperty {
+        name: GenerationParameter,
+        property: &'static str,
+        value: f32,
+    },
+    /// A property of the [ErosionProperties] is out of its range,
+    /// `expected` describes the range.
+    InvalidErosionProperty {
+        property: &'static str,
+        value: f32,
+        expected: &'static str,
+    },
+    /// The droplets and the thermal passes can reach past the eroded region,
+    /// `required` is `droplet_lifetime + thermal_iterations`, see [ErosionProperties::padding].
+    ErosionPaddingTooSmall {
+        padding: usize,
+        required: usize,
+    },
+    /// The range of the parameter is reversed, or one of its ends is NaN.
+    InvalidParameterRange {
+        parameter: GenerationParameter,
+        min: f32,
+        max: f32,
+    },
 }
 
 impl std::fmt::Display for GenerationError {
@@ -116,6 +836,73 @@ impl std::fmt::Display for GenerationError {
             GenerationError::NoiseNotFound { name } => {
                 write!(f, "Parameter named {} not found", name)
             }
+            GenerationError::GenerationNotFound { id } => {
+                write!(f, "Generation {:?} not found", id)
+            }
+            GenerationError::ParameterNotFound { id } => {
+                write!(f, "Parameter {:?} not found", id)
+            }
+            GenerationError::NoiseInUse { name } => {
+                write!(f, "Noise {} is used by a generation or the erosion", name)
+            }
+            GenerationError::InvalidOrder { id } => {
+                write!(f, "Generation {:?} can not be placed before its parent", id)
+            }
+            GenerationError::IndexOutOfRange { index, len } => write!(
+                f,
+                "Position {} is out of range, there are {} entries",
+                index, len
+            ),
+            GenerationError::DuplicateNoise { name } => {
+                write!(f, "Noise named {} already exists", name)
+            }
+            GenerationError::InvalidNoiseRange {
+                name,
+                min_value,
+                max_value,
+            } => write!(
+                f,
+                "Noise {} has invalid values from {} to {}",
+                name, min_value, max_value
+            ),
+            GenerationError::InvalidNoiseScale { name, scale } => write!(
+                f,
+                "Noise {} has invalid scale {}, it must be positive",
+                name, scale
+            ),
+            GenerationError::ZeroOctaves { name } => {
+                write!(f, "Noise {} must have at least one octave", name)
+            }
+            GenerationError::NonFiniteNoiseProperty {
+                name,
+                property,
+                value,
+            } => write!(f, "Noise {} has invalid {} {}", name, property, value),
+            GenerationError::InvalidErosionProperty {
+                property,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Erosion has invalid {} {}, it must be {}",
+                property, value, expected
+            ),
+            GenerationError::ErosionPaddingTooSmall { padding, required } => write!(
+                f,
+                "Erosion padding {} is smaller than the droplet lifetime plus the thermal iterations {}",
+                padding, required
+            ),
+            GenerationError::InvalidParameterRange {
+                parameter,
+                min,
+                max,
+            } => write!(
+                f,
+                "Parameter {} has invalid range from {} to {}",
+                parameter, min, max
+            ),
         }
     }
 }
+
+impl std::error::Error for GenerationError {}
@@ -0,0 +1,133 @@
+use super::*;
+
+/// Distance (in tiles) between the samples of the coarse search.
+const LOCATE_COARSE_STEP: i64 = 4;
+
+/// The result of [WorldGenerator::locate].
+#[derive(Debug, Clone)]
+pub struct Located<T> {
+    /// The center of the found tile.
+    pub position: Vector2<f32>,
+    /// The distance from the search origin to `position`.
+    pub distance: f32,
+    pub sample: PointSample<T>,
+}
+
+impl<T: Copy> WorldGenerator<T> {
+    /// Find the nearest tile, for which the predicate returns `true`,
+    /// no further than `max_radius` from the position.
+    ///
+    /// The search first walks outward in a spiral sampling only every few tiles
+    /// to find out how far the nearest match is, and then checks every tile
+    /// inside that distance, so if the only matches are smaller than the coarse step,
+    /// they might be missed. The second pass samples every tile of that circle,
+    /// its cost grows with the square of the distance to the match.
+    ///
+    /// Without erosion the tiles are sampled one by one and no chunks are generated.
+    /// With erosion every chunk the search passes through, that is not generated yet,
+    /// is generated and eroded (see [ErosionProperties]), which is expensive
+    /// for a large `max_radius`. Only the last few of them are kept in memory,
+    /// and none are added to the world.
+    pub fn locate(
+        &self,
+        position: Vector2<f32>,
+        max_radius: f32,
+        predicate: impl Fn(&PointSample<T>) -> bool,
+    ) -> Option<Located<T>> {
+        let mut sampler = Sampler::new(self);
+        let origin = TilePos::from_world(position, self.tile_size);
+        let min_tile_size = self.tile_size.x.min(self.tile_size.y);
+
+        // Coarse pass: find the distance to the nearest coarse match
+        let max_ring = (max_radius / (min_tile_size * LOCATE_COARSE_STEP as f32)).ceil() as i64;
+        let mut radius = None;
+        for ring in 0..=max_ring {
+            for cell in ring_cells(ring) {
+                let tile = Vector2::new(
+                    origin.x + cell.x * LOCATE_COARSE_STEP,
+                    origin.y + cell.y * LOCATE_COARSE_STEP,
+                );
+                let distance = self.tile_distance(position, tile);
+                if distance <= max_radius
+                    && radius.is_none_or(|radius| distance < radius)
+                    && predicate(&sampler.sample(tile))
+                {
+                    radius = Some(distance);
+                }
+            }
+            if radius.is_some_and(|radius| {
+                radius <= (ring as f32 + 0.5) * LOCATE_COARSE_STEP as f32 * min_tile_size
+            }) {
+                // All further cells are further away
+                break;
+            }
+        }
+        let radius = radius?;
+
+        // Fine pass: check every tile closer than the coarse match
+        let max_ring = (radius / min_tile_size).ceil() as i64 + 1;
+        let mut best: Option<Located<T>> = None;
+        for ring in 0..=max_ring {
+            for tile in ring_cells(ring) {
+                let tile = Vector2::new(origin.x + tile.x, origin.y + tile.y);
+                let distance = self.tile_distance(position, tile);
+                if distance > radius || best.as_ref().is_some_and(|best| best.distance <= distance)
+                {
+                    continue;
+                }
+                let sample = sampler.sample(tile);
+                if predicate(&sample) {
+                    best = Some(Located {
+                        position: tile.world_center(self.tile_size),
+                        distance,
+                        sample,
+                    });
+                }
+            }
+            if best
+                .as_ref()
+                .is_some_and(|best| best.distance <= (ring as f32 + 0.5) * min_tile_size)
+            {
+                break;
+            }
+        }
+        best
+    }
+
+    fn tile_distance(&self, position: Vector2<f32>, tile: TilePos) -> f32 {
+        let delta = tile.world_center(self.tile_size) - position;
+        (delta.x * delta.x + delta.y * delta.y).sqrt()
+    }
+}
+
+/// The cells forming a square ring around the origin, `ring` cells away from it.
+pub(crate) fn ring_cells(ring: i64) -> impl Iterator<Item = Vector2<i64>> {
+    // The rows include the corners, the columns are between them
+    let rows = (-ring..=ring).flat_map(move |x| [Vector2::new(x, -ring), Vector2::new(x, ring)]);
+    let columns =
+        (1 - ring..ring).flat_map(move |y| [Vector2::new(-ring, y), Vector2::new(ring, y)]);
+    // The ring 0 is a single cell, that is both of its rows
+    let len = if ring == 0 { 1 } else { 8 * ring as usize };
+    rows.chain(columns).take(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn rings_have_every_cell_once() {
+        assert_eq!(ring_cells(0).collect::<Vec<_>>(), [Vector2::new(0, 0)]);
+        for ring in 1..5 {
+            let cells: Vec<_> = ring_cells(ring).collect();
+            let unique: HashSet<_> = cells.iter().copied().collect();
+            assert_eq!(cells.len(), 8 * ring as usize);
+            assert_eq!(unique.len(), cells.len());
+            assert!(cells
+                .iter()
+                .all(|cell| cell.x.abs().max(cell.y.abs()) == ring));
+        }
+    }
+}
@@ -1,87 +1,569 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use super::*;
 
+mod background;
+mod builder;
+mod cache;
 mod chunk;
+mod edit;
+mod erosion;
+mod events;
+#[allow(clippy::module_inception)]
 mod generator;
+mod locate;
 mod multi_noise;
+mod parameters;
+mod sample;
+mod spawn;
+mod storage;
+mod supersampling;
 mod tile_generation;
 mod view;
 
-use chunk::*;
-use generator::*;
+use background::*;
+pub use builder::*;
+pub use cache::*;
+pub use chunk::*;
+use edit::*;
+pub use erosion::*;
+pub use events::*;
+pub use generator::*;
+pub use locate::*;
 pub use multi_noise::*;
+use parameters::*;
+pub use sample::*;
+pub use spawn::*;
+pub use storage::*;
+pub use supersampling::*;
 pub use tile_generation::*;
 pub use view::*;
 
-const CHUNK_WIDTH: usize = 50;
-const CHUNK_HEIGHT: usize = 50;
-const CHUNK_SIZE: Vector2<usize> = Vector2::new(CHUNK_WIDTH, CHUNK_HEIGHT);
+/// Number of tiles along each side of a chunk, unless changed with [WorldGenerator::set_chunk_size].
+pub const DEFAULT_CHUNK_SIZE: Vector2<usize> = Vector2::new(50, 50);
+
+/// Size of a tile in world units, unless changed with [WorldGenerator::set_scale].
+pub const DEFAULT_TILE_SIZE: Vector2<f32> = Vector2::new(2.0, 2.0);
+
+/// The coarsest level of detail, see [WorldGenerator::lod_tile_size].
+pub const MAX_LOD_LEVEL: u32 = 16;
+
+/// Version of the generated worlds.
+///
+/// The same config generates the same chunks bit for bit, on every platform,
+/// with any number of threads, in the foreground or in the background.
+/// With erosion the chunks also depend on the chunk size, since the erosion
+/// is simulated per chunk (see [WorldGenerator::set_chunk_size]), so then
+/// the same chunks are only generated with the same chunk size.
+/// The version is increased by every change that alters the chunks generated
+/// from an existing config: the noise sampling, the classification of the tiles,
+/// the erosion or the supersampling. Optimizations, new APIs and new options,
+/// that are disabled by default, must not alter the chunks and keep the version.
+///
+/// The version is a part of the [WorldGenerator::fingerprint], so the chunks
+/// stored by an older version are not loaded. The guarantee is checked by the tests
+/// in `tests/determinism.rs` against the chunk hashes recorded for this version.
+pub const GENERATION_VERSION: u32 = 1;
 
 pub struct WorldGenerator<T> {
-    pub generator: Generator<T>,
+    /// Shared with the background workers, see [WorldGenerator::request_area].
+    generator: Arc<Generator<T>>,
+    /// The revisions of the generator, with which the chunks were generated,
+    /// see [Generator::tiles_revision].
+    generator_revision: u64,
+    tiles_revision: u64,
     tile_size: Vector2<f32>,
-    chunks: HashMap<Vector2<i32>, ChunkGeneration<T, CHUNK_WIDTH, CHUNK_HEIGHT>>,
+    supersampling: Supersampling,
+    chunk_size: Vector2<usize>,
+    retain_parameters: bool,
+    chunks: ChunkCache<T>,
+    /// See [WorldGenerator::set_tile].
+    edits: TileEdits<T>,
+    cache_policy: CachePolicy,
+    focus_points: Vec<Vector2<f32>>,
+    storage: Option<Arc<ChunkStorage<T>>>,
+    /// The config hash of the storage for a changed world, see [WorldGenerator::set_storage].
+    storage_key: Option<fn(&WorldGenerator<T>) -> u64>,
+    storage_errors: Vec<StorageError>,
+    /// Used to generate the chunks synchronously, the global pool if `None`.
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Number of the background workers, the number of CPUs if `None`.
+    background_threads: Option<usize>,
+    /// Started on the first background request.
+    workers: Option<ChunkWorkers<T>>,
+    /// The chunks of the last [WorldGenerator::request_area],
+    /// they are not evicted when the background chunks arrive.
+    requested: Option<(Scale, ChunkPos, ChunkPos)>,
+    /// Increased every time the generated chunks are cleared,
+    /// so that the chunks requested before that are discarded.
+    epoch: u64,
+    /// See [WorldGenerator::subscribe].
+    subscribers: Subscribers,
+}
+
+impl<T> Default for WorldGenerator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> WorldGenerator<T> {
     pub fn new() -> Self {
         Self {
-            generator: Generator::new(),
-            tile_size: Vector2::new(2.0, 2.0),
-            chunks: HashMap::new(),
+            generator: Arc::new(Generator::new()),
+            generator_revision: 0,
+            tiles_revision: 0,
+            tile_size: DEFAULT_TILE_SIZE,
+            supersampling: Supersampling::None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            retain_parameters: false,
+            chunks: ChunkCache::new(),
+            edits: TileEdits::new(),
+            cache_policy: CachePolicy::default(),
+            focus_points: Vec::new(),
+            storage: None,
+            storage_key: None,
+            storage_errors: Vec::new(),
+            thread_pool: None,
+            background_threads: None,
+            workers: None,
+            requested: None,
+            epoch: 0,
+            subscribers: Vec::new(),
         }
     }
 
+    pub fn generator(&self) -> &Generator<T> {
+        &self.generator
+    }
+
+    /// Replace the generator. Clears all previous generations,
+    /// and the storage keeps the chunks of the new generator separately,
+    /// see [WorldGenerator::set_storage].
+    pub fn set_generator(&mut self, generator: Generator<T>) {
+        self.generator_revision = generator.revision();
+        self.tiles_revision = generator.tiles_revision();
+        self.generator = Arc::new(generator);
+        self.clear_chunks();
+        self.rekey_storage();
+    }
+
     pub fn tile_size(&self) -> Vector2<f32> {
         self.tile_size
     }
 
-    fn tile_to_chunk_pos(&self, tile_position: Vector2<f32>) -> Vector2<i32> {
-        Vector2::new(
-            (tile_position.x / (CHUNK_WIDTH as f32 * self.tile_size.x)).floor() as i32,
-            (tile_position.y / (CHUNK_HEIGHT as f32 * self.tile_size.y)).floor() as i32,
+    /// How the tiles of the current scale are sampled, see [GenerationScale::Supersampled].
+    pub fn supersampling(&self) -> Supersampling {
+        self.supersampling
+    }
+
+    /// Number of tiles along each side of a chunk.
+    pub fn chunk_size(&self) -> Vector2<usize> {
+        self.chunk_size
+    }
+
+    /// Change the number of tiles along each side of a chunk.
+    /// Clears all previous generations, the edits are kept.
+    /// Erosion is simulated per chunk, so with erosion enabled
+    /// the generation slightly depends on the chunk size.
+    pub fn set_chunk_size(&mut self, chunk_size: Vector2<usize>) {
+        assert!(
+            chunk_size.x > 0 && chunk_size.y > 0,
+            "Chunk size must be positive, got ({}, {})",
+            chunk_size.x,
+            chunk_size.y
+        );
+        if self.chunk_size != chunk_size {
+            self.clear_chunks();
+            self.chunk_size = chunk_size;
+            self.rekey_storage();
+        }
+    }
+
+    /// The scale of the chunks with the current tile size and supersampling.
+    pub(crate) fn scale(&self) -> Scale {
+        Scale::new(self.tile_size, self.supersampling)
+    }
+
+    /// The scale of the level of detail, see [Supersampling::lod].
+    /// The level 0 is the current scale.
+    fn lod_scale(&self, level: u32) -> Scale {
+        if level == 0 {
+            return self.scale();
+        }
+        Scale::new(self.lod_tile_size(level), self.supersampling.lod(level))
+    }
+
+    /// The tile size of the level of detail `level`: the tiles of every level
+    /// are twice as big as the tiles of the previous one, starting with [WorldGenerator::tile_size].
+    pub fn lod_tile_size(&self, level: u32) -> Vector2<f32> {
+        self.tile_size * 2f32.powi(level.min(MAX_LOD_LEVEL) as i32)
+    }
+
+    /// The coarsest level of detail with tiles no bigger than `resolution` world units,
+    /// or the level 0 if the current tiles are already bigger than that.
+    pub fn lod_level(&self, resolution: f32) -> u32 {
+        let ratio = resolution / self.tile_size.x.max(self.tile_size.y);
+        if ratio >= 2.0 {
+            (ratio.log2().floor() as u32).min(MAX_LOD_LEVEL)
+        } else {
+            0
+        }
+    }
+
+    /// The first and the last chunk containing the tiles of the area.
+    /// The end of the area is exclusive, for an empty area the last chunk is before the first.
+    fn tile_area_chunks(&self, area: Area<i64>) -> (ChunkPos, ChunkPos) {
+        if area.end.x <= area.start.x || area.end.y <= area.start.y {
+            return (Vector2::new(0, 0), Vector2::new(-1, -1));
+        }
+        (
+            ChunkPos::containing(area.start, self.chunk_size),
+            ChunkPos::containing(area.end - Vector2::new(1, 1), self.chunk_size),
         )
     }
 
-    /// Change the generation scale. Clears all previous generations.
+    /// Change the generation scale. The chunks generated with the previous scales are kept,
+    /// so changing the scale back does not generate them again.
+    /// The edits stay in place, see [WorldGenerator::set_tile].
+    /// Panics if the number of samples is out of range, see [Supersampling::is_valid].
     pub fn set_scale(&mut self, new_scale: GenerationScale) {
-        self.chunks.clear();
-        self.tile_size = new_scale.tile_size();
+        let (tile_size, supersampling) = new_scale.tile_size_and_supersampling();
+        assert!(
+            supersampling.is_valid(),
+            "Supersampling must take from 1 to {} samples, got {}",
+            MAX_SUPERSAMPLES,
+            supersampling.samples()
+        );
+        self.tile_size = tile_size;
+        self.supersampling = supersampling;
+        self.rekey_storage();
+    }
+
+    /// Whether the parameter values of every tile are kept in the generated chunks,
+    /// so that they can be accessed through [GenerationView::tiles_with_parameters].
+    pub fn retain_parameters(&self) -> bool {
+        self.retain_parameters
+    }
+
+    /// Change whether the parameter values are kept.
+    /// Clears all previous generations if the setting changes.
+    pub fn set_retain_parameters(&mut self, retain: bool) {
+        if self.retain_parameters != retain {
+            self.clear_chunks();
+            self.retain_parameters = retain;
+        }
+    }
+
+    /// Discard the chunks affected by the changes of the generator
+    /// made through [WorldGenerator::generator_mut] and rekey the storage.
+    fn invalidate_outdated(&mut self) {
+        if self.generator.revision() != self.generator_revision {
+            let tiles_changed = self.generator.tiles_revision() != self.tiles_revision;
+            self.generator_revision = self.generator.revision();
+            self.tiles_revision = self.generator.tiles_revision();
+            if tiles_changed || self.retain_parameters {
+                self.clear_chunks();
+            } else if let Some(workers) = &mut self.workers {
+                workers.forget_failed();
+            }
+            self.rekey_storage();
+        }
+    }
+
+    /// Get the generated chunk and mark it as used.
+    pub(crate) fn cached_chunk(&self, key: ChunkKey) -> Option<&ChunkGeneration<T>> {
+        self.chunks.get(key)
+    }
+
+    /// Generate the chunks synchronously in the thread pool, instead of the global one.
+    pub fn set_thread_pool(&mut self, pool: Option<Arc<rayon::ThreadPool>>) {
+        self.thread_pool = pool;
+    }
+
+    /// Change the number of threads generating the chunks in the background,
+    /// `None` means the number of CPUs. Cancels the background requests.
+    pub fn set_background_threads(&mut self, threads: Option<usize>) {
+        self.background_threads = threads;
+        self.workers = None;
+    }
+
+    /// Clear the generated chunks and cancel the background requests.
+    fn clear_chunks(&mut self) {
+        let cleared = self.chunks.clear();
+        self.emit_evicted(cleared);
+        self.epoch += 1;
+        if let Some(workers) = &mut self.workers {
+            workers.retain(|_| false);
+        }
     }
 }
 
-impl<T: Copy> WorldGenerator<T> {
+impl<T: Hash> WorldGenerator<T> {
+    /// A hash of the generator and the scale, see [Generator::fingerprint],
+    /// and of the chunk size, if the erosion is enabled.
+    /// Stays the same between runs and platforms, so it can be used
+    /// to identify the stored chunks, see [ChunkStorage::new].
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.generator.hash_into(&mut hasher);
+        hasher.write_f32(self.tile_size.x);
+        hasher.write_f32(self.tile_size.y);
+        hasher.write(self.supersampling.path_suffix().as_bytes());
+        if self.generator.has_erosion() {
+            hasher.write_u64(self.chunk_size.x as u64);
+            hasher.write_u64(self.chunk_size.y as u64);
+        }
+        hasher.finish()
+    }
+}
+
+impl<T: Clone> WorldGenerator<T> {
+    /// Access the generator to change it. If it was changed, once the returned guard
+    /// is dropped, the chunks affected by the changes are discarded (with the
+    /// [ChunkEventKind::Evicted] events), and the storage keeps the chunks
+    /// of the changed generator separately, see [WorldGenerator::set_storage].
+    /// The changes, that keep the generated tiles (e.g. [Generator::move_noise]),
+    /// only affect the chunks retaining the parameter values.
+    /// If the generator is still used by the background workers, it is cloned first.
+    pub fn generator_mut(&mut self) -> GeneratorMut<'_, T> {
+        GeneratorMut { world: self }
+    }
+}
+
+/// Changes the generator of a [WorldGenerator], see [WorldGenerator::generator_mut].
+pub struct GeneratorMut<'a, T: Clone> {
+    world: &'a mut WorldGenerator<T>,
+}
+
+impl<T: Clone> Deref for GeneratorMut<'_, T> {
+    type Target = Generator<T>;
+
+    fn deref(&self) -> &Generator<T> {
+        &self.world.generator
+    }
+}
+
+impl<T: Clone> DerefMut for GeneratorMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Generator<T> {
+        Arc::make_mut(&mut self.world.generator)
+    }
+}
+
+impl<T: Clone> Drop for GeneratorMut<'_, T> {
+    fn drop(&mut self) {
+        self.world.invalidate_outdated();
+    }
+}
+
+impl<T: Copy + Send + Sync> WorldGenerator<T> {
     /// Generate a rectangular area and return its view. The generation might be bigger
     /// (but not smaller) than requested because it generates chunks.
-    pub fn generate_area(&mut self, area: Area<f32>) -> GenerationView<T> {
-        let start = self.tile_to_chunk_pos(area.start);
-        let end = self.tile_to_chunk_pos(area.end);
+    /// The missing chunks are loaded from the [ChunkStorage] if possible,
+    /// otherwise they are generated in parallel and saved.
+    /// Afterwards the chunks outside of the area are evicted according to the [CachePolicy].
+    ///
+    /// The area is generated with the coarsest level of detail with tiles no bigger
+    /// than `resolution` world units (see [WorldGenerator::lod_level]), so with
+    /// a `resolution` no bigger than [WorldGenerator::tile_size] it is the current scale.
+    /// An overview of a big area is generated much faster this way.
+    /// The levels are cached separately, so the overview and the details of the same area can coexist.
+    /// The levels are supersampled according to [Supersampling::lod].
+    pub fn generate_area(&mut self, area: Area<f32>, resolution: f32) -> GenerationView<'_, T> {
+        let scale = self.lod_scale(self.lod_level(resolution));
+        let (start, end) = world_area_chunks(area, self.chunk_size, scale.tile_size());
+        self.generate_chunks(scale, start, end);
+        self.view_chunks(scale, start, end)
+    }
+
+    /// Same as [WorldGenerator::generate_area], but for the tiles from `area.start`
+    /// to `area.end`, with `area.end` excluded.
+    pub fn generate_tiles(&mut self, area: Area<i64>) -> GenerationView<'_, T> {
+        let (start, end) = self.tile_area_chunks(area);
+        self.generate_chunks(self.scale(), start, end);
+        self.view_chunks(self.scale(), start, end)
+    }
+
+    /// Generate the missing chunks of the scale from `start` to `end` inclusive.
+    fn generate_chunks(&mut self, scale: Scale, start: ChunkPos, end: ChunkPos) {
+        let missing: Vec<ChunkPos> = (start.y..=end.y)
+            .flat_map(|y| (start.x..=end.x).map(move |x| Vector2::new(x, y)))
+            .filter(|&chunk_pos| !self.chunks.contains((scale, chunk_pos)))
+            .collect();
+
+        let generator = &self.generator;
+        let storage = self.storage.as_deref();
+        let chunk_size = self.chunk_size;
+        let retain_parameters = self.retain_parameters;
+        let generate = || -> Vec<_> {
+            missing
+                .into_par_iter()
+                .map(|chunk_pos| {
+                    let loaded = load_or_generate(
+                        generator,
+                        storage,
+                        chunk_pos,
+                        chunk_size,
+                        scale,
+                        retain_parameters,
+                    );
+                    (chunk_pos, loaded)
+                })
+                .collect()
+        };
+        let generated = match &self.thread_pool {
+            Some(pool) => pool.install(generate),
+            None => generate(),
+        };
+        for (chunk_pos, loaded) in generated {
+            let kind = loaded.event_kind();
+            let chunk = self.store_loaded(loaded);
+            self.chunks.insert((scale, chunk_pos), chunk);
+            self.emit(kind, (scale, chunk_pos));
+        }
+        self.enforce_cache_policy(Some((scale, start, end)));
+    }
+}
+
+impl<T: Copy + Send + Sync + 'static> WorldGenerator<T> {
+    /// Request the area to be generated in the background without blocking.
+    /// The missing chunks closest to `focus` are generated first,
+    /// the already requested chunks are reprioritized.
+    /// Use [WorldGenerator::poll_generated] to receive the finished chunks,
+    /// until then [WorldGenerator::view] does not include them.
+    /// If the generation of a chunk panics, the [ChunkEventKind::Failed] event is sent instead.
+    pub fn request_area(&mut self, area: Area<f32>, focus: Vector2<f32>) {
+        let (start, end) = world_area_chunks(area, self.chunk_size, self.tile_size);
+        let scale = self.scale();
+        self.requested = Some((scale, start, end));
+        let chunk_size = self.chunk_size.map(|x| x as f32);
+        let priority = move |(scale, chunk_pos): ChunkKey| {
+            let center = (chunk_pos.map(|x| x as f32) + Vector2::new(0.5, 0.5))
+                * chunk_size
+                * scale.tile_size();
+            let delta = center - focus;
+            delta.x * delta.x + delta.y * delta.y
+        };
 
+        let threads = self
+            .background_threads
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+        let workers = self
+            .workers
+            .get_or_insert_with(|| ChunkWorkers::new(threads));
+        workers.reprioritize(priority);
         for y in start.y..=end.y {
             for x in start.x..=end.x {
                 let chunk_pos = Vector2::new(x, y);
-                self.chunks
-                    .entry(chunk_pos)
-                    .or_insert_with(|| self.generator.generate_chunk(chunk_pos, self.tile_size));
+                if !self.chunks.contains((scale, chunk_pos)) {
+                    workers.request(Job {
+                        chunk_pos,
+                        priority: priority((scale, chunk_pos)),
+                        epoch: self.epoch,
+                        generator: Arc::clone(&self.generator),
+                        storage: self.storage.clone(),
+                        chunk_size: self.chunk_size,
+                        scale,
+                        retain_parameters: self.retain_parameters,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Cancel the background requests of the chunks, that are completely outside of the area.
+    /// The chunks that are already being generated are still finished.
+    /// The failed chunks outside of the area can be requested again.
+    pub fn cancel_requests_outside(&mut self, area: Area<f32>) {
+        let chunk_size = self.chunk_size;
+        if let Some(workers) = &mut self.workers {
+            workers.retain(|(scale, chunk_pos)| {
+                let (start, end) = world_area_chunks(area, chunk_size, scale.tile_size());
+                chunk_in_range(chunk_pos, start, end)
+            });
+        }
+    }
+
+    /// Store the chunks finished in the background since the last call
+    /// and return the positions of those with the current scale.
+    pub fn poll_generated(&mut self) -> Vec<ChunkPos> {
+        let workers = match &mut self.workers {
+            Some(workers) => workers,
+            None => return Vec::new(),
+        };
+        let mut generated = Vec::new();
+        for finished in workers.finished() {
+            let key = (finished.scale, finished.chunk_pos);
+            if finished.epoch != self.epoch || self.chunks.contains(key) {
+                // Requested before the chunks were cleared, or generated synchronously meanwhile
+                continue;
             }
+            let loaded = match finished.loaded {
+                Some(loaded) => loaded,
+                None => {
+                    self.emit(ChunkEventKind::Failed, key);
+                    continue;
+                }
+            };
+            let kind = loaded.event_kind();
+            let chunk = self.store_loaded(loaded);
+            self.chunks.insert(key, chunk);
+            self.emit(kind, key);
+            generated.push(key);
         }
+        self.enforce_cache_policy(self.requested);
+        let scale = self.scale();
+        generated
+            .into_iter()
+            .filter(|&key| key.0 == scale && self.chunks.contains(key))
+            .map(|(_, chunk_pos)| chunk_pos)
+            .collect()
+    }
 
-        self.view(area)
+    /// Number of chunks requested in the background, that are not polled yet.
+    pub fn pending_chunks(&self) -> usize {
+        self.workers
+            .as_ref()
+            .map_or(0, |workers| workers.pending_count())
     }
+}
 
+impl<T: Copy> WorldGenerator<T> {
     /// View the generated area.
-    pub fn view(&self, area: Area<f32>) -> GenerationView<T> {
-        let start = self.tile_to_chunk_pos(area.start);
-        let end = self.tile_to_chunk_pos(area.end);
+    pub fn view(&self, area: Area<f32>) -> GenerationView<'_, T> {
+        let (start, end) = world_area_chunks(area, self.chunk_size, self.tile_size);
+        self.view_chunks(self.scale(), start, end)
+    }
+
+    /// View the generated area with the level of detail `level`, see [WorldGenerator::lod_tile_size].
+    pub fn view_lod(&self, area: Area<f32>, level: u32) -> GenerationView<'_, T> {
+        let scale = self.lod_scale(level);
+        let (start, end) = world_area_chunks(area, self.chunk_size, scale.tile_size());
+        self.view_chunks(scale, start, end)
+    }
+
+    /// View the generated tiles from `area.start` to `area.end`, with `area.end` excluded.
+    pub fn view_tiles(&self, area: Area<i64>) -> GenerationView<'_, T> {
+        let (start, end) = self.tile_area_chunks(area);
+        self.view_chunks(self.scale(), start, end)
+    }
 
+    /// View the generated chunks of the scale from `start` to `end` inclusive.
+    /// The edits are mapped into the scale, see [WorldGenerator::set_tile].
+    fn view_chunks(&self, scale: Scale, start: ChunkPos, end: ChunkPos) -> GenerationView<'_, T> {
         let dx = end.x - start.x + 1;
         let dy = end.y - start.y + 1;
         if dx <= 0 || dy <= 0 {
             // Negative area
             return GenerationView {
-                chunk_size: CHUNK_SIZE,
-                tile_size: self.tile_size,
+                chunk_size: self.chunk_size,
+                tile_size: scale.tile_size(),
                 chunks: Vec::new(),
             };
         }
@@ -91,30 +573,55 @@ impl<T: Copy> WorldGenerator<T> {
         for y in start.y..=end.y {
             for x in start.x..=end.x {
                 let chunk_pos = Vector2::new(x, y);
-                if let Some(chunk) = self.chunks.get(&chunk_pos) {
-                    visible_chunks.push((chunk_pos, chunk));
+                if let Some(chunk) = self.cached_chunk((scale, chunk_pos)) {
+                    let edits = self.chunk_edits(scale, chunk_pos);
+                    visible_chunks.push((chunk_pos, chunk, edits));
                 }
             }
         }
 
         GenerationView {
-            chunk_size: CHUNK_SIZE,
-            tile_size: self.tile_size,
+            chunk_size: self.chunk_size,
+            tile_size: scale.tile_size(),
             chunks: visible_chunks,
         }
     }
 }
 
+/// The first and the last chunk intersecting the world area.
+fn world_area_chunks(
+    area: Area<f32>,
+    chunk_size: Vector2<usize>,
+    tile_size: Vector2<f32>,
+) -> (ChunkPos, ChunkPos) {
+    (
+        ChunkPos::containing(TilePos::from_world(area.start, tile_size), chunk_size),
+        ChunkPos::containing(TilePos::from_world(area.end, tile_size), chunk_size),
+    )
+}
+
 /// Describes the scale of the generation.
 pub enum GenerationScale {
     /// Bigger tile size -> faster generation
     TileSize { x: f32, y: f32 },
+    /// Same as [GenerationScale::TileSize], but every tile is sampled at multiple points,
+    /// so that the coarse tiles agree with the finer ones, see [Supersampling].
+    Supersampled {
+        x: f32,
+        y: f32,
+        supersampling: Supersampling,
+    },
 }
 
 impl GenerationScale {
-    fn tile_size(self) -> Vector2<f32> {
+    fn tile_size_and_supersampling(self) -> (Vector2<f32>, Supersampling) {
         match self {
-            GenerationScale::TileSize { x, y } => Vector2::new(x, y),
+            GenerationScale::TileSize { x, y } => (Vector2::new(x, y), Supersampling::None),
+            GenerationScale::Supersampled {
+                x,
+                y,
+                supersampling,
+            } => (Vector2::new(x, y), supersampling),
         }
     }
 }
@@ -2,6 +2,7 @@ use super::*;
 
 pub struct MultiNoise {
     noise: Box<dyn noise::NoiseFn<[f64; 2]> + Sync + Send>,
+    seed: u32,
     properties: MultiNoiseProperties,
 }
 
@@ -10,9 +11,24 @@ impl MultiNoise {
         use noise::Seedable;
         Self {
             noise: Box::new(::noise::OpenSimplex::new().set_seed(seed)),
-            properties: properties,
+            seed,
+            properties,
         }
     }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn properties(&self) -> &MultiNoiseProperties {
+        &self.properties
+    }
+
+    /// Relative cost of sampling the noise.
+    pub(crate) fn cost(&self) -> usize {
+        self.properties.octaves
+    }
+
     pub fn get(&self, pos: Vector2<f32>) -> f32 {
         let mut frequency = 1.0;
         let mut amplitude = 1.0;
@@ -27,13 +43,19 @@ impl MultiNoise {
             frequency *= self.properties.lacunarity;
             amplitude *= self.properties.persistance;
         }
-        let value = value.max(-1.0).min(1.0);
+        let value = value.clamp(-1.0, 1.0);
         (value / 2.0 + 0.5) * (self.properties.max_value - self.properties.min_value)
             + self.properties.min_value
     }
 }
 
-#[derive(Debug, Clone)]
+impl Clone for MultiNoise {
+    fn clone(&self) -> Self {
+        Self::new(self.seed, self.properties.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultiNoiseProperties {
     pub min_value: f32,
     pub max_value: f32,
@@ -42,3 +64,63 @@ pub struct MultiNoiseProperties {
     pub lacunarity: f32,
     pub persistance: f32,
 }
+
+impl MultiNoiseProperties {
+    /// Hash the values of the properties, see [Generator::fingerprint].
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        // Destructured, so that the new fields are not forgotten
+        let Self {
+            min_value,
+            max_value,
+            scale,
+            octaves,
+            lacunarity,
+            persistance,
+        } = *self;
+        hasher.write_f32(min_value);
+        hasher.write_f32(max_value);
+        hasher.write_f32(scale);
+        hasher.write_usize(octaves);
+        hasher.write_f32(lacunarity);
+        hasher.write_f32(persistance);
+    }
+
+    /// All problems with the properties of the noise called `name`.
+    pub fn problems(&self, name: &str) -> Vec<GenerationError> {
+        let mut problems = Vec::new();
+        if !self.min_value.is_finite()
+            || !self.max_value.is_finite()
+            || self.min_value > self.max_value
+        {
+            problems.push(GenerationError::InvalidNoiseRange {
+                name: name.to_owned(),
+                min_value: self.min_value,
+                max_value: self.max_value,
+            });
+        }
+        if !self.scale.is_finite() || self.scale <= 0.0 {
+            problems.push(GenerationError::InvalidNoiseScale {
+                name: name.to_owned(),
+                scale: self.scale,
+            });
+        }
+        if self.octaves == 0 {
+            problems.push(GenerationError::ZeroOctaves {
+                name: name.to_owned(),
+            });
+        }
+        for (property, value) in [
+            ("lacunarity", self.lacunarity),
+            ("persistance", self.persistance),
+        ] {
+            if !value.is_finite() {
+                problems.push(GenerationError::NonFiniteNoiseProperty {
+                    name: name.to_owned(),
+                    property,
+                    value,
+                });
+            }
+        }
+        problems
+    }
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+/// Parameter values of a single tile, that are only sampled when needed.
+pub(crate) struct LazyParameters<'a> {
+    noises: &'a [MultiNoise],
+    position: Vector2<f32>,
+    values: Vec<f32>,
+    evaluated: Vec<bool>,
+}
+
+impl<'a> LazyParameters<'a> {
+    pub(crate) fn new(noises: &'a [MultiNoise]) -> Self {
+        Self {
+            noises,
+            position: Vector2::new(0.0, 0.0),
+            values: vec![0.0; noises.len()],
+            evaluated: vec![false; noises.len()],
+        }
+    }
+
+    /// Forget all values and move to another position.
+    pub(crate) fn reset(&mut self, position: Vector2<f32>) {
+        self.position = position;
+        self.evaluated.fill(false);
+    }
+
+    /// Override the value of the parameter, for example with the eroded one.
+    pub(crate) fn set(&mut self, id: ParameterId, value: f32) {
+        self.values[id.index()] = value;
+        self.evaluated[id.index()] = true;
+    }
+
+    /// Set the values of the parameters that are not evaluated yet, indexed by [ParameterId].
+    pub(crate) fn fill_unevaluated(&mut self, values: &[f32]) {
+        for (index, &value) in values.iter().enumerate() {
+            if !self.evaluated[index] {
+                self.values[index] = value;
+                self.evaluated[index] = true;
+            }
+        }
+    }
+
+    pub(crate) fn is_evaluated(&self, id: ParameterId) -> bool {
+        self.evaluated[id.index()]
+    }
+
+    pub(crate) fn get(&mut self, id: ParameterId) -> f32 {
+        let index = id.index();
+        if !self.evaluated[index] {
+            self.values[index] = self.noises[index].get(self.position);
+            self.evaluated[index] = true;
+        }
+        self.values[index]
+    }
+
+    /// Evaluate all parameters, indexed by [ParameterId].
+    pub(crate) fn evaluate_all(&mut self) -> &[f32] {
+        for index in 0..self.values.len() {
+            if !self.evaluated[index] {
+                self.values[index] = self.noises[index].get(self.position);
+                self.evaluated[index] = true;
+            }
+        }
+        &self.values
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+/// The generation and the parameter values of a single tile.
+#[derive(Debug, Clone)]
+pub struct PointSample<T> {
+    pub tile: Tile<T>,
+    /// Indexed by [ParameterId].
+    values: Vec<f32>,
+}
+
+impl<T> PointSample<T> {
+    pub fn parameters(&self) -> ParameterValues<'_> {
+        ParameterValues(&self.values)
+    }
+}
+
+impl<T: Copy> Generator<T> {
+    /// Sample a single tile without generating the whole chunk, if possible.
+    /// With erosion enabled the tile depends on its surroundings,
+    /// so the chunk containing it is generated.
+    pub(crate) fn sample_tile(
+        &self,
+        tile: TilePos,
+        chunk_size: Vector2<usize>,
+        scale: Scale,
+    ) -> PointSample<T> {
+        if self.has_erosion() {
+            let (chunk_pos, _) = tile.to_chunk(chunk_size);
+            let chunk = self.generate_chunk(
+                chunk_pos,
+                chunk_size,
+                scale.tile_size(),
+                scale.supersampling(),
+                true,
+            );
+            return self.sample_chunk(&chunk, tile, chunk_size, scale);
+        }
+
+        let area = tile.world_area(scale.tile_size());
+        let (tile, values) = self.generate_with_parameters(area, None, scale.supersampling());
+        PointSample { tile, values }
+    }
+
+    /// Take the sample of a tile from a generated chunk.
+    /// The parameters, that the chunk did not retain, are computed for the tile alone,
+    /// using the eroded value kept by the chunk, so nothing else is generated.
+    pub(crate) fn sample_chunk(
+        &self,
+        chunk: &ChunkGeneration<T>,
+        tile: TilePos,
+        chunk_size: Vector2<usize>,
+        scale: Scale,
+    ) -> PointSample<T> {
+        let (_, local) = tile.to_chunk(chunk_size);
+        let (&generated, parameters) = chunk.get(local);
+        if let Some(parameters) = parameters {
+            return PointSample {
+                tile: generated,
+                values: parameters.as_slice().to_vec(),
+            };
+        }
+        let eroded = chunk.eroded(local);
+        if self.has_erosion() && eroded.is_none() {
+            return PointSample {
+                tile: generated,
+                ..self.sample_tile(tile, chunk_size, scale)
+            };
+        }
+        let area = tile.world_area(scale.tile_size());
+        let (_, values) = self.generate_with_parameters(area, eroded, scale.supersampling());
+        PointSample {
+            tile: generated,
+            values,
+        }
+    }
+}
+
+impl<T: Copy> WorldGenerator<T> {
+    /// Get the generation and the parameter values of the tile at the position.
+    /// Uses the generated chunks when possible, otherwise samples the tile directly.
+    /// The tile is edited if it was changed with [WorldGenerator::set_tile],
+    /// while the parameters are always the generated ones.
+    pub fn sample(&self, position: Vector2<f32>) -> PointSample<T> {
+        self.sample_tile(TilePos::from_world(position, self.tile_size))
+    }
+
+    pub(crate) fn sample_tile(&self, tile: TilePos) -> PointSample<T> {
+        let (chunk_pos, _) = tile.to_chunk(self.chunk_size);
+        let sample = match self.cached_chunk((self.scale(), chunk_pos)) {
+            Some(chunk) => self
+                .generator
+                .sample_chunk(chunk, tile, self.chunk_size, self.scale()),
+            None => self
+                .generator
+                .sample_tile(tile, self.chunk_size, self.scale()),
+        };
+        self.apply_edit(tile, sample)
+    }
+
+    fn apply_edit(&self, tile: TilePos, sample: PointSample<T>) -> PointSample<T> {
+        match self.edited_tile(tile) {
+            Some(&edited) => PointSample {
+                tile: edited,
+                ..sample
+            },
+            None => sample,
+        }
+    }
+
+    /// Same as [WorldGenerator::sample], but for many positions at once.
+    /// The samples are returned in the same order as the positions.
+    pub fn sample_many(&self, positions: &[Vector2<f32>]) -> Vec<PointSample<T>> {
+        let mut sampler = Sampler::new(self);
+        positions
+            .iter()
+            .map(|&position| sampler.sample(TilePos::from_world(position, self.tile_size)))
+            .collect()
+    }
+}
+
+/// How many chunks generated by a [Sampler] are kept at a time.
+const SAMPLER_MAX_CHUNKS: usize = 64;
+
+/// Samples many tiles, generating the chunks that have to be generated
+/// for that (with erosion) only once, as long as the samples are close to each other:
+/// at most [SAMPLER_MAX_CHUNKS] of them are kept, the oldest ones are dropped first.
+pub(crate) struct Sampler<'a, T> {
+    world: &'a WorldGenerator<T>,
+    generated: HashMap<ChunkPos, ChunkGeneration<T>>,
+    /// The generated chunks from the oldest to the newest.
+    order: VecDeque<ChunkPos>,
+}
+
+impl<'a, T: Copy> Sampler<'a, T> {
+    pub(crate) fn new(world: &'a WorldGenerator<T>) -> Self {
+        Self {
+            world,
+            generated: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn sample(&mut self, tile: TilePos) -> PointSample<T> {
+        let world = self.world;
+        if !world.generator.has_erosion() {
+            return world.sample_tile(tile);
+        }
+
+        // With erosion every missing chunk has to be generated anyway
+        let (chunk_pos, _) = tile.to_chunk(world.chunk_size);
+        if world.cached_chunk((world.scale(), chunk_pos)).is_some() {
+            return world.sample_tile(tile);
+        }
+        if !self.generated.contains_key(&chunk_pos) {
+            if self.order.len() >= SAMPLER_MAX_CHUNKS {
+                let oldest = self.order.pop_front().unwrap();
+                self.generated.remove(&oldest);
+            }
+            let chunk = world.generator.generate_chunk(
+                chunk_pos,
+                world.chunk_size,
+                world.tile_size,
+                world.supersampling,
+                false,
+            );
+            self.generated.insert(chunk_pos, chunk);
+            self.order.push_back(chunk_pos);
+        }
+        let chunk = &self.generated[&chunk_pos];
+        let sample = world
+            .generator
+            .sample_chunk(chunk, tile, world.chunk_size, world.scale());
+        world.apply_edit(tile, sample)
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::*;
+
+/// Maximum number of the sampled tiles remembered by [WorldGenerator::find_spawn].
+const SPAWN_MAX_TILES: usize = 1 << 16;
+
+type TilePredicate<'a, T> = Box<dyn Fn(&TileValue<T>) -> bool + 'a>;
+
+/// Describes where a spawn point may be. Used in [WorldGenerator::find_spawn].
+/// All distances are measured in tiles.
+pub struct SpawnCriteria<'a, T> {
+    standing_on: TilePredicate<'a, T>,
+    near: Option<(TilePredicate<'a, T>, usize)>,
+    land: TilePredicate<'a, T>,
+    min_land: Option<(usize, usize)>,
+    min_island_size: Option<usize>,
+    search_radius: usize,
+}
+
+impl<'a, T> SpawnCriteria<'a, T> {
+    /// The spawn tile has to satisfy the predicate.
+    pub fn new(standing_on: impl Fn(&TileValue<T>) -> bool + 'a) -> Self {
+        Self {
+            standing_on: Box::new(standing_on),
+            near: None,
+            land: Box::new(|_| true),
+            min_land: None,
+            min_island_size: None,
+            search_radius: 1000,
+        }
+    }
+
+    /// Some tile within `distance` from the spawn has to satisfy the predicate.
+    pub fn near(mut self, predicate: impl Fn(&TileValue<T>) -> bool + 'a, distance: usize) -> Self {
+        self.near = Some((Box::new(predicate), distance));
+        self
+    }
+
+    /// Which tiles count as land. By default every generated tile does.
+    pub fn land(mut self, predicate: impl Fn(&TileValue<T>) -> bool + 'a) -> Self {
+        self.land = Box::new(predicate);
+        self
+    }
+
+    /// At least `count` land tiles have to be within `radius` from the spawn.
+    pub fn min_land_in_radius(mut self, radius: usize, count: usize) -> Self {
+        self.min_land = Some((radius, count));
+        self
+    }
+
+    /// The spawn has to be on a connected piece of land of at least `tiles` tiles.
+    pub fn min_island_size(mut self, tiles: usize) -> Self {
+        self.min_island_size = Some(tiles);
+        self
+    }
+
+    /// How far from the origin the spawn is searched for, along each axis:
+    /// the searched area is a square with the side of `2 * tiles + 1` tiles
+    /// centered at the origin. Defaults to 1000 tiles.
+    pub fn search_radius(mut self, tiles: usize) -> Self {
+        self.search_radius = tiles;
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum SpawnError {
+    NotFound { search_radius: usize },
+}
+
+impl std::fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnError::NotFound { search_radius } => write!(
+                f,
+                "No spawn point satisfies the criteria within {} tiles along each axis from the origin",
+                search_radius
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+impl<T: Copy> WorldGenerator<T> {
+    /// Find a spawn point satisfying the criteria.
+    /// The search walks outward from the origin in square rings, up to
+    /// [SpawnCriteria::search_radius], so the same world and criteria always give the same spawn.
+    /// Returns the center of the spawn tile.
+    pub fn find_spawn(&self, criteria: &SpawnCriteria<T>) -> Result<Vector2<f32>, SpawnError> {
+        let mut search = SpawnSearch {
+            sampler: Sampler::new(self),
+            tiles: HashMap::new(),
+        };
+        for ring in 0..=criteria.search_radius as i64 {
+            for tile in ring_cells(ring) {
+                if search.is_spawn(tile, criteria) {
+                    return Ok(tile.world_center(self.tile_size));
+                }
+            }
+        }
+        Err(SpawnError::NotFound {
+            search_radius: criteria.search_radius,
+        })
+    }
+}
+
+struct SpawnSearch<'a, T> {
+    sampler: Sampler<'a, T>,
+    /// Tiles that have already been sampled, forgotten all at once
+    /// when there are [SPAWN_MAX_TILES] of them.
+    tiles: HashMap<TilePos, Tile<T>>,
+}
+
+impl<T: Copy> SpawnSearch<'_, T> {
+    fn tile(&mut self, tile: TilePos) -> Tile<T> {
+        if self.tiles.len() >= SPAWN_MAX_TILES && !self.tiles.contains_key(&tile) {
+            self.tiles.clear();
+        }
+        let sampler = &mut self.sampler;
+        *self
+            .tiles
+            .entry(tile)
+            .or_insert_with(|| sampler.sample(tile).tile)
+    }
+
+    fn check(&mut self, tile: TilePos, predicate: &TilePredicate<T>) -> bool {
+        self.tile(tile).is_some_and(|tile| predicate(&tile))
+    }
+
+    fn is_spawn(&mut self, tile: TilePos, criteria: &SpawnCriteria<T>) -> bool {
+        if !self.check(tile, &criteria.standing_on) || !self.check(tile, &criteria.land) {
+            return false;
+        }
+
+        if let Some((predicate, distance)) = &criteria.near {
+            let found = tiles_in_radius(tile, *distance).any(|tile| self.check(tile, predicate));
+            if !found {
+                return false;
+            }
+        }
+
+        if let Some((radius, count)) = criteria.min_land {
+            let land = tiles_in_radius(tile, radius)
+                .filter(|&tile| self.check(tile, &criteria.land))
+                .count();
+            if land < count {
+                return false;
+            }
+        }
+
+        if let Some(size) = criteria.min_island_size {
+            // Flood fill the land until enough tiles are found
+            let mut visited = HashSet::from([tile]);
+            let mut queue = VecDeque::from([tile]);
+            while let Some(tile) = queue.pop_front() {
+                if visited.len() >= size {
+                    break;
+                }
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let next = Vector2::new(tile.x + dx, tile.y + dy);
+                    if !visited.contains(&next) && self.check(next, &criteria.land) {
+                        visited.insert(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if visited.len() < size {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// All tiles within the radius from the center, sorted in a spiral.
+fn tiles_in_radius(center: TilePos, radius: usize) -> impl Iterator<Item = TilePos> {
+    let radius = radius as i64;
+    (0..=radius)
+        .flat_map(ring_cells)
+        .filter(move |delta| delta.x * delta.x + delta.y * delta.y <= radius * radius)
+        .map(move |delta| Vector2::new(center.x + delta.x, center.y + delta.y))
+}
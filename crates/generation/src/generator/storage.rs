@@ -0,0 +1,541 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::*;
+
+/// Number of chunks along each side of a region.
+const REGION_SIZE: i32 = 16;
+const REGION_MAGIC: &[u8; 8] = b"ISLANDRG";
+const REGION_VERSION: u32 = 3;
+/// Magic, version and config hash.
+const HEADER_LEN: u64 = 8 + 4 + 8;
+/// Offset and length of every chunk in the region.
+const INDEX_ENTRY_LEN: u64 = 8 + 4;
+/// Header and the index of all chunks, the chunks are stored after that.
+const DATA_START: u64 = HEADER_LEN + INDEX_ENTRY_LEN * (REGION_SIZE * REGION_SIZE) as u64;
+
+/// Stores the generated chunks on disk, so that they are loaded
+/// instead of being generated again, see [WorldGenerator::set_storage].
+///
+/// The chunks are grouped into region files of 16x16 chunks.
+/// Every region file starts with a header and an index of the chunks in it,
+/// followed by the encoded chunks. The files are stored in a subdirectory
+/// named after the config hash, the scale and the chunk size, so the chunks of
+/// different generators never mix.
+///
+/// A saved chunk is appended to the region and only then the index is updated,
+/// so an interrupted save leaves the previous version of the chunk.
+/// When the replaced chunks take more space than the current ones,
+/// the region is compacted. New and compacted regions are written
+/// to a temporary file first, and then renamed into place.
+/// A region with a damaged header is started anew by the next save.
+pub struct ChunkStorage<T> {
+    directory: PathBuf,
+    config_hash: u64,
+    encode: fn(&ChunkGeneration<T>) -> Vec<u8>,
+    decode: fn(&[u8]) -> Result<ChunkGeneration<T>, String>,
+    /// Region files are read and written by one thread at a time.
+    lock: Mutex<()>,
+}
+
+impl<T: Serialize + DeserializeOwned> ChunkStorage<T> {
+    /// `config_hash` identifies the generator, see [WorldGenerator::fingerprint] or [GeneratorConfig::hash].
+    pub fn new(directory: impl Into<PathBuf>, config_hash: u64) -> Self {
+        Self {
+            directory: directory.into(),
+            config_hash,
+            encode: ChunkGeneration::encode,
+            decode: ChunkGeneration::decode,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl<T> ChunkStorage<T> {
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn config_hash(&self) -> u64 {
+        self.config_hash
+    }
+
+    /// The same storage for the chunks of a different generator.
+    fn with_config_hash(&self, config_hash: u64) -> Self {
+        Self {
+            directory: self.directory.clone(),
+            config_hash,
+            encode: self.encode,
+            decode: self.decode,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn region_path(
+        &self,
+        chunk_pos: ChunkPos,
+        chunk_size: Vector2<usize>,
+        scale: Scale,
+    ) -> PathBuf {
+        let region = Vector2::new(
+            chunk_pos.x.div_euclid(REGION_SIZE),
+            chunk_pos.y.div_euclid(REGION_SIZE),
+        );
+        let tile_size = scale.tile_size();
+        self.directory
+            .join(format!(
+                "{:016x}-{}x{}-{}x{}{}",
+                self.config_hash,
+                tile_size.x,
+                tile_size.y,
+                chunk_size.x,
+                chunk_size.y,
+                scale.supersampling().path_suffix()
+            ))
+            .join(format!("r.{}.{}.region", region.x, region.y))
+    }
+
+    /// Load the chunk, returns `None` if it is not stored.
+    pub(crate) fn load(
+        &self,
+        chunk_pos: ChunkPos,
+        chunk_size: Vector2<usize>,
+        scale: Scale,
+    ) -> Result<Option<ChunkGeneration<T>>, StorageError> {
+        let path = self.region_path(chunk_pos, chunk_size, scale);
+        let _lock = self.lock();
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(StorageError::Io { path, error }),
+        };
+
+        let bytes = (|| {
+            self.check_header(&mut file)?;
+            file.seek(SeekFrom::Start(index_position(chunk_pos)))?;
+            let offset = read_u64(&mut file)?;
+            let len = read_u32(&mut file)? as usize;
+            if len == 0 {
+                return Ok(None);
+            }
+            file.seek(SeekFrom::Start(offset))?;
+            let mut bytes = vec![0; len];
+            file.read_exact(&mut bytes)?;
+            Ok(Some(bytes))
+        })()
+        .map_err(|error| StorageError::from_io(path.clone(), error))?;
+
+        let chunk = match bytes {
+            Some(bytes) => (self.decode)(&bytes),
+            None => return Ok(None),
+        };
+        match chunk {
+            Ok(chunk) if chunk.size() == chunk_size => Ok(Some(chunk)),
+            Ok(_) => Err(StorageError::Corrupted {
+                path,
+                message: "The chunk size does not match".to_owned(),
+            }),
+            Err(message) => Err(StorageError::Corrupted { path, message }),
+        }
+    }
+
+    /// Write the chunk, replacing the previously stored one.
+    pub(crate) fn save(
+        &self,
+        chunk_pos: ChunkPos,
+        scale: Scale,
+        chunk: &ChunkGeneration<T>,
+    ) -> Result<(), StorageError> {
+        let path = self.region_path(chunk_pos, chunk.size(), scale);
+        let bytes = (self.encode)(chunk);
+        let _lock = self.lock();
+
+        (|| {
+            let mut file = self.open_region(&path)?;
+            let offset = file.seek(SeekFrom::End(0))?;
+            file.write_all(&bytes)?;
+            // The index is updated after the chunk is written completely
+            file.seek(SeekFrom::Start(index_position(chunk_pos)))?;
+            file.write_all(&offset.to_le_bytes())?;
+            file.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            self.compact_if_wasteful(&path, file)
+        })()
+        .map_err(|error| StorageError::from_io(path, error))
+    }
+
+    /// Open the region for writing, creating it if it does not exist or is damaged.
+    fn open_region(&self, path: &Path) -> std::io::Result<File> {
+        let open = || OpenOptions::new().read(true).write(true).open(path);
+        let mut file = match open() {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                write_atomically(path, &self.empty_region())?;
+                return open();
+            }
+            Err(error) => return Err(error),
+        };
+        match self.check_header(&mut file) {
+            Ok(()) => Ok(file),
+            Err(error) if is_damaged(&error) => {
+                // Nothing can be loaded from the region anyway
+                drop(file);
+                write_atomically(path, &self.empty_region())?;
+                open()
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// The header and the index of a region without chunks.
+    fn empty_region(&self) -> Vec<u8> {
+        let mut region = Vec::with_capacity(DATA_START as usize);
+        region.extend_from_slice(REGION_MAGIC);
+        region.extend_from_slice(&REGION_VERSION.to_le_bytes());
+        region.extend_from_slice(&self.config_hash.to_le_bytes());
+        region.resize(DATA_START as usize, 0);
+        region
+    }
+
+    /// Rewrite the region without the replaced chunks,
+    /// if they take more space than the chunks in the index.
+    fn compact_if_wasteful(&self, path: &Path, mut file: File) -> std::io::Result<()> {
+        file.seek(SeekFrom::Start(HEADER_LEN))?;
+        let mut index = Vec::with_capacity((REGION_SIZE * REGION_SIZE) as usize);
+        for _ in 0..REGION_SIZE * REGION_SIZE {
+            index.push((read_u64(&mut file)?, read_u32(&mut file)?));
+        }
+        let used: u64 = index.iter().map(|&(_, len)| len as u64).sum();
+        let unused = file.metadata()?.len().saturating_sub(DATA_START + used);
+        if unused <= used {
+            return Ok(());
+        }
+
+        let mut region = self.empty_region();
+        for (entry, &(offset, len)) in index.iter().enumerate() {
+            if len == 0 {
+                continue;
+            }
+            let mut bytes = vec![0; len as usize];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut bytes)?;
+            let position = (HEADER_LEN + INDEX_ENTRY_LEN * entry as u64) as usize;
+            let new_offset = region.len() as u64;
+            region[position..position + 8].copy_from_slice(&new_offset.to_le_bytes());
+            region[position + 8..position + 12].copy_from_slice(&len.to_le_bytes());
+            region.extend_from_slice(&bytes);
+        }
+        drop(file);
+        write_atomically(path, &region)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // The lock protects no data, so it can not be left inconsistent by a panic
+        self.lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn check_header(&self, file: &mut File) -> std::io::Result<()> {
+        file.seek(SeekFrom::Start(0))?;
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != REGION_MAGIC {
+            return Err(invalid_data("Not a region file"));
+        }
+        let version = read_u32(file)?;
+        if version != REGION_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported region version {}",
+                version
+            )));
+        }
+        let config_hash = read_u64(file)?;
+        if config_hash != self.config_hash {
+            return Err(invalid_data(format!(
+                "The region belongs to the config {:016x}",
+                config_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Write the file next to the path and rename it into place,
+/// so that the file at the path is never partially written.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let temporary = path.with_extension("region.tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temporary, path)
+}
+
+/// Whether the error means that the region can not be read, see [StorageError::from_io].
+fn is_damaged(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+fn index_position(chunk_pos: ChunkPos) -> u64 {
+    let local = Vector2::new(
+        chunk_pos.x.rem_euclid(REGION_SIZE),
+        chunk_pos.y.rem_euclid(REGION_SIZE),
+    );
+    HEADER_LEN + INDEX_ENTRY_LEN * (local.y * REGION_SIZE + local.x) as u64
+}
+
+fn read_u32(file: &mut File) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    file.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(file: &mut File) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Corrupted {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl StorageError {
+    fn from_io(path: PathBuf, error: std::io::Error) -> Self {
+        if is_damaged(&error) {
+            StorageError::Corrupted {
+                path,
+                message: error.to_string(),
+            }
+        } else {
+            StorageError::Io { path, error }
+        }
+    }
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Io { path, error } => {
+                write!(f, "Failed to access {}: {}", path.display(), error)
+            }
+            StorageError::Corrupted { path, message } => {
+                write!(f, "Corrupted region file {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Io { error, .. } => Some(error),
+            StorageError::Corrupted { .. } => None,
+        }
+    }
+}
+
+/// The result of [load_or_generate].
+pub(crate) struct LoadedChunk<T> {
+    pub chunk: ChunkGeneration<T>,
+    /// Whether the chunk was generated rather than loaded.
+    pub generated: bool,
+    /// The errors of loading and saving the chunk.
+    pub errors: Vec<StorageError>,
+}
+
+impl<T> LoadedChunk<T> {
+    pub(crate) fn event_kind(&self) -> ChunkEventKind {
+        if self.generated {
+            ChunkEventKind::Generated
+        } else {
+            ChunkEventKind::Loaded
+        }
+    }
+}
+
+/// Load the chunk from the storage, or generate it and save it, if it is not stored.
+/// Failing to load the chunk is not fatal, the chunk is generated instead.
+/// Runs on the thread generating the chunk, so the main thread does not wait for the disk.
+pub(crate) fn load_or_generate<T: Copy>(
+    generator: &Generator<T>,
+    storage: Option<&ChunkStorage<T>>,
+    chunk_pos: ChunkPos,
+    chunk_size: Vector2<usize>,
+    scale: Scale,
+    retain_parameters: bool,
+) -> LoadedChunk<T> {
+    let mut errors = Vec::new();
+    if let Some(storage) = storage {
+        match storage.load(chunk_pos, chunk_size, scale) {
+            Ok(Some(mut chunk)) => {
+                if !retain_parameters {
+                    chunk.discard_parameters();
+                }
+                // Chunks stored without the parameters are generated again when they are needed
+                if chunk.has_parameters() || !retain_parameters {
+                    return LoadedChunk {
+                        chunk,
+                        generated: false,
+                        errors,
+                    };
+                }
+            }
+            Ok(None) => {}
+            Err(error) => errors.push(error),
+        }
+    }
+    let chunk = generator.generate_chunk(
+        chunk_pos,
+        chunk_size,
+        scale.tile_size(),
+        scale.supersampling(),
+        retain_parameters,
+    );
+    if let Some(storage) = storage {
+        if let Err(error) = storage.save(chunk_pos, scale, &chunk) {
+            errors.push(error);
+        }
+    }
+    LoadedChunk {
+        chunk,
+        generated: true,
+        errors,
+    }
+}
+
+impl<T: Hash> WorldGenerator<T> {
+    /// Store the generated chunks on disk and load them from there
+    /// instead of generating again. `None` disables the storage.
+    ///
+    /// The storage has to be created for the same generator, otherwise
+    /// the stored chunks of a different generator will be loaded.
+    /// Use [WorldGenerator::fingerprint] as the config hash of the storage to make sure of that.
+    /// When the generator, the scale or the chunk size changes, the storage is rekeyed
+    /// with the new [WorldGenerator::fingerprint], so the chunks of different worlds never mix.
+    pub fn set_storage(&mut self, storage: Option<ChunkStorage<T>>) {
+        self.storage = storage.map(Arc::new);
+        self.storage_key = Some(WorldGenerator::fingerprint);
+    }
+}
+
+impl<T> WorldGenerator<T> {
+    pub fn storage(&self) -> Option<&ChunkStorage<T>> {
+        self.storage.as_deref()
+    }
+
+    /// Keep the chunks of the current generator in the same directory,
+    /// but separately from the chunks of the previous one, see [WorldGenerator::set_storage].
+    pub(crate) fn rekey_storage(&mut self) {
+        if let (Some(storage), Some(key)) = (&self.storage, self.storage_key) {
+            let storage = storage.with_config_hash(key(self));
+            self.storage = Some(Arc::new(storage));
+        }
+    }
+
+    /// Get the errors of the storage since the last call.
+    /// Failing to load or save a chunk does not stop the generation,
+    /// the chunk is generated (or just kept in memory) instead.
+    pub fn take_storage_errors(&mut self) -> Vec<StorageError> {
+        std::mem::take(&mut self.storage_errors)
+    }
+
+    /// Remember the storage errors of the chunk, it was already saved if needed.
+    pub(crate) fn store_loaded(&mut self, loaded: LoadedChunk<T>) -> ChunkGeneration<T> {
+        self.storage_errors.extend(loaded.errors);
+        loaded.chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: Vector2<usize> = Vector2::new(4, 4);
+
+    fn storage(name: &str) -> ChunkStorage<u8> {
+        let directory = std::env::temp_dir().join(format!(
+            "generation-storage-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        ChunkStorage::new(directory, 42)
+    }
+
+    fn chunk(value: u8) -> ChunkGeneration<u8> {
+        let tile = Some(TileValue {
+            parent: value,
+            child: None,
+        });
+        ChunkGeneration::new(CHUNK_SIZE, vec![tile; CHUNK_SIZE.x * CHUNK_SIZE.y], None, 0)
+    }
+
+    fn scale() -> Scale {
+        Scale::new(Vector2::new(1.0, 1.0), Supersampling::None)
+    }
+
+    fn load(storage: &ChunkStorage<u8>, chunk_pos: ChunkPos) -> Option<u8> {
+        let chunk = storage.load(chunk_pos, CHUNK_SIZE, scale()).unwrap()?;
+        Some(chunk.get(Vector2::new(0, 0)).0.unwrap().parent)
+    }
+
+    #[test]
+    fn saving_again_does_not_grow_the_region() {
+        let storage = storage("resave");
+        let chunk_pos = Vector2::new(-1, 3);
+        let path = storage.region_path(chunk_pos, CHUNK_SIZE, scale());
+        storage.save(chunk_pos, scale(), &chunk(0)).unwrap();
+        let chunk_len = std::fs::metadata(&path).unwrap().len() - DATA_START;
+
+        for value in 1..20 {
+            storage.save(chunk_pos, scale(), &chunk(value)).unwrap();
+            assert_eq!(load(&storage, chunk_pos), Some(value));
+            let len = std::fs::metadata(&path).unwrap().len();
+            assert!(
+                len <= DATA_START + 2 * chunk_len,
+                "The region has grown to {}",
+                len
+            );
+        }
+        assert!(!path.with_extension("region.tmp").exists());
+        std::fs::remove_dir_all(storage.directory()).unwrap();
+    }
+
+    #[test]
+    fn damaged_region_is_started_anew() {
+        let storage = storage("damaged");
+        let chunk_pos = Vector2::new(0, 0);
+        storage.save(chunk_pos, scale(), &chunk(1)).unwrap();
+        let path = storage.region_path(chunk_pos, CHUNK_SIZE, scale());
+        // As if the program crashed while writing the header
+        std::fs::write(&path, &REGION_MAGIC[..5]).unwrap();
+
+        assert!(matches!(
+            storage.load(chunk_pos, CHUNK_SIZE, scale()),
+            Err(StorageError::Corrupted { .. })
+        ));
+        storage.save(chunk_pos, scale(), &chunk(2)).unwrap();
+        assert_eq!(load(&storage, chunk_pos), Some(2));
+        assert_eq!(load(&storage, Vector2::new(1, 0)), None);
+        std::fs::remove_dir_all(storage.directory()).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use super::*;
+
+/// Maximum number of samples along each side of a tile, see [Supersampling::lod].
+pub const MAX_SUPERSAMPLES: usize = 16;
+
+/// How the tiles of a coarse scale are sampled, see [GenerationScale::Supersampled].
+///
+/// Without supersampling every tile is classified by the noise values at its corner,
+/// so with big tiles the small features are either missed or blown up.
+/// The samples of a tile are placed on a grid starting at its corner, so with
+/// `samples = n` the tile of size `s` samples the same points as the tiles of size `s / n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Supersampling {
+    /// Sample the corner of the tile.
+    #[default]
+    None,
+    /// Classify `samples` x `samples` points of the tile and take the most common generation.
+    /// The retained parameter values are averaged.
+    Majority { samples: usize },
+    /// Average the parameter values of `samples` x `samples` points of the tile
+    /// and classify the average.
+    Average { samples: usize },
+}
+
+impl Supersampling {
+    /// Number of samples along each side of a tile.
+    /// The generators only accept the valid numbers, see [Supersampling::is_valid].
+    pub fn samples(self) -> usize {
+        match self {
+            Supersampling::None => 1,
+            Supersampling::Majority { samples } | Supersampling::Average { samples } => samples,
+        }
+    }
+
+    /// Whether the number of samples is from 1 to [MAX_SUPERSAMPLES].
+    pub fn is_valid(self) -> bool {
+        match self {
+            Supersampling::None => true,
+            Supersampling::Majority { samples } | Supersampling::Average { samples } => {
+                (1..=MAX_SUPERSAMPLES).contains(&samples)
+            }
+        }
+    }
+
+    /// The supersampling of the level of detail `level`, see [WorldGenerator::lod_tile_size].
+    /// The tiles of the level are `2^level` times bigger, so they take `2^level` times
+    /// more samples along each side (up to [MAX_SUPERSAMPLES]). That way with `samples = 1`
+    /// every tile of the level samples the corners of the tiles of the level 0 it covers.
+    pub fn lod(self, level: u32) -> Self {
+        let samples = |samples: usize| {
+            samples
+                .saturating_mul(1 << level.min(MAX_LOD_LEVEL))
+                .min(MAX_SUPERSAMPLES)
+        };
+        match self {
+            Supersampling::None => Supersampling::None,
+            Supersampling::Majority { samples: n } => Supersampling::Majority {
+                samples: samples(n),
+            },
+            Supersampling::Average { samples: n } => Supersampling::Average {
+                samples: samples(n),
+            },
+        }
+    }
+
+    /// Distinguishes the stored chunks of different modes, see [ChunkStorage].
+    pub(crate) fn path_suffix(self) -> String {
+        match self {
+            Supersampling::None => String::new(),
+            Supersampling::Majority { .. } => format!("-majority{}", self.samples()),
+            Supersampling::Average { .. } => format!("-average{}", self.samples()),
+        }
+    }
+}
+
+/// Counts the votes of the samples of a tile for [Supersampling::Majority].
+/// Every key votes with the value it was first added with.
+pub(crate) struct Votes<K, V> {
+    /// The order of the first appearance and the number of the votes for every key.
+    votes: HashMap<K, (usize, usize, V)>,
+}
+
+impl<K: Eq + Hash, V> Votes<K, V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            votes: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, key: K, value: V) {
+        let appearance = self.votes.len();
+        self.votes.entry(key).or_insert((appearance, 0, value)).1 += 1;
+    }
+
+    /// The value of the most common key, `None` if there are no votes.
+    /// The earliest key wins the ties, so that they are deterministic.
+    pub(crate) fn winner(self) -> Option<V> {
+        self.votes
+            .into_values()
+            .max_by_key(|&(appearance, count, _)| (count, std::cmp::Reverse(appearance)))
+            .map(|(_, _, value)| value)
+    }
+}
+
+impl<T: Copy> Generator<T> {
+    /// Classify the tile covering the world area. `eroded` overrides the value
+    /// of the eroded parameter at every sample. If `evaluate` is `true`,
+    /// afterwards `noise_values` can be evaluated to the parameter values of the tile:
+    /// the averaged ones when supersampling.
+    pub(crate) fn generate_tile(
+        &self,
+        noise_values: &mut LazyParameters,
+        area: Area<f32>,
+        eroded: Option<(ParameterId, f32)>,
+        supersampling: Supersampling,
+        evaluate: bool,
+    ) -> Tile<T> {
+        let reset = |noise_values: &mut LazyParameters, position| {
+            noise_values.reset(position);
+            if let Some((parameter, value)) = eroded {
+                noise_values.set(parameter, value);
+            }
+        };
+
+        let samples = supersampling.samples();
+        if samples == 1 {
+            reset(noise_values, area.start);
+            return self.generate(noise_values);
+        }
+
+        let step = (area.end - area.start) / samples as f32;
+        let positions = (0..samples)
+            .flat_map(|y| (0..samples).map(move |x| Vector2::new(x, y)))
+            .map(|sample| area.start + sample.map(|x| x as f32) * step);
+        let mut sums = vec![0.0; self.parameter_count()];
+        let mut add_values = |noise_values: &mut LazyParameters| {
+            for (sum, value) in sums.iter_mut().zip(noise_values.evaluate_all()) {
+                *sum += value;
+            }
+        };
+
+        let majority = if let Supersampling::Majority { .. } = supersampling {
+            let mut votes = Votes::new();
+            for position in positions {
+                reset(noise_values, position);
+                let (tile, key) = self.generate_keyed(noise_values);
+                votes.add(key, tile);
+                if evaluate {
+                    add_values(noise_values);
+                }
+            }
+            votes.winner()
+        } else {
+            for position in positions {
+                reset(noise_values, position);
+                add_values(noise_values);
+            }
+            None
+        };
+
+        if evaluate || majority.is_none() {
+            // Leave the averaged values, except for the eroded one
+            reset(noise_values, area.start);
+            let count = (samples * samples) as f32;
+            let averages: Vec<f32> = sums.iter().map(|sum| sum / count).collect();
+            noise_values.fill_unevaluated(&averages);
+        }
+        majority.unwrap_or_else(|| self.generate(noise_values))
+    }
+}
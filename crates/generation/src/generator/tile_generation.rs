@@ -7,12 +7,38 @@ pub struct TileGeneration {
     pub parameter_values: HashMap<GenerationParameter, ParameterRange>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ParameterRange {
     pub(crate) min: f32,
     pub(crate) max: f32,
 }
 
+impl ParameterRange {
+    pub fn min(self) -> f32 {
+        self.min
+    }
+
+    pub fn max(self) -> f32 {
+        self.max
+    }
+
+    /// Check that the range of the parameter is not reversed and has no NaNs.
+    /// Infinite ends are allowed to leave the range open.
+    pub fn check(self, parameter: &str) -> Result<(), GenerationError> {
+        if self.min <= self.max {
+            Ok(())
+        } else {
+            Err(GenerationError::InvalidParameterRange {
+                parameter: parameter.to_owned(),
+                min: self.min,
+                max: self.max,
+            })
+        }
+    }
+}
+
+/// The range is kept as given, a reversed one is reported
+/// when the generation is added, see [ParameterRange::check].
 impl From<RangeInclusive<f32>> for ParameterRange {
     fn from(range: RangeInclusive<f32>) -> Self {
         Self {
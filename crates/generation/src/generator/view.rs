@@ -3,52 +3,90 @@ use super::*;
 pub struct GenerationView<'a, T> {
     pub chunk_size: Vector2<usize>,
     pub tile_size: Vector2<f32>,
-    pub(crate) chunks: Vec<(
-        Vector2<i32>,
-        &'a ChunkGeneration<T, CHUNK_WIDTH, CHUNK_HEIGHT>,
-    )>,
+    pub(crate) chunks: Vec<ViewChunk<'a, T>>,
 }
 
+/// A chunk with its position and the edits made in it.
+pub(crate) type ViewChunk<'a, T> = (ChunkPos, &'a ChunkGeneration<T>, Option<ChunkEdits<T>>);
+
 impl<'a, T> GenerationView<'a, T> {
     /// Get an iterator over all tiles.
     /// The tile is returned as a tuple (tile_area, &tile)
     /// where tile_area is the **global** position and size of the tile
-    /// (or the area that this tile covers),
-    /// and tile is the generated tile.
+    /// (or the area that this tile covers, the same as [TilePos::world_area]),
+    /// and tile is the generated tile, or the edited one (see [WorldGenerator::set_tile]).
     pub fn tiles(&'a self) -> impl Iterator<Item = (Area<f32>, &'a Tile<T>)> + 'a {
-        let chunk_size = CHUNK_SIZE.map(|x| x as f32);
-        self.chunks()
-            .map(move |(chunk_pos, tiles)| {
-                let tile_start = chunk_size * chunk_pos.map(|x| x as f32);
-                tiles.map(move |(position, tile)| {
-                    let position = position.map(|x| x as f32) * self.tile_size;
-                    (
-                        Area {
-                            start: tile_start,
-                            end: tile_start + position,
-                        },
-                        tile,
-                    )
-                })
+        self.tiles_with_parameters()
+            .map(|(tile_area, tile, _)| (tile_area, tile))
+    }
+
+    /// Same as [GenerationView::tiles], but also yields the parameter values of the tiles.
+    /// The parameters are `None` unless the generator retains them
+    /// (see [WorldGenerator::set_retain_parameters]).
+    pub fn tiles_with_parameters(
+        &'a self,
+    ) -> impl Iterator<Item = (Area<f32>, &'a Tile<T>, Option<ParameterValues<'a>>)> + 'a {
+        let chunk_size = self.chunk_size.map(|x| x as f32);
+        self.chunks
+            .iter()
+            .flat_map(move |(chunk_pos, chunk_gen, edits)| {
+                let chunk_start = chunk_size * chunk_pos.map(|x| x as f32);
+                chunk_gen
+                    .iter_with_parameters()
+                    .map(move |(position, tile, parameters)| {
+                        let tile = edited(edits.as_ref(), position, tile);
+                        let start = (chunk_start + position.map(|x| x as f32)) * self.tile_size;
+                        (
+                            Area {
+                                start,
+                                end: start + self.tile_size,
+                            },
+                            tile,
+                            parameters,
+                        )
+                    })
             })
-            .flatten()
+    }
+
+    /// Same as [GenerationView::tiles], but with the positions of the tiles
+    /// on the tile grid instead of their world areas.
+    pub fn grid_tiles(&'a self) -> impl Iterator<Item = (TilePos, &'a Tile<T>)> + 'a {
+        self.chunks().flat_map(move |(chunk_pos, tiles)| {
+            tiles.map(move |(local, tile)| {
+                (TilePos::from_chunk(chunk_pos, local, self.chunk_size), tile)
+            })
+        })
     }
 
     /// Get an iterator over all chunks with their positions.
     /// The tile is returned as a tuple (tile_pos, &tile)
     /// where tile_pos is the **local** position of the tile
     /// (or the area that this tile covers),
-    /// and tile is the generated tile.
+    /// and tile is the generated tile, or the edited one.
     pub fn chunks(
         &'a self,
     ) -> impl Iterator<
         Item = (
-            Vector2<i32>,
+            ChunkPos,
             impl Iterator<Item = (Vector2<usize>, &'a Tile<T>)>,
         ),
     > {
-        self.chunks
-            .iter()
-            .map(|&(chunk_pos, chunk_gen)| (chunk_pos, chunk_gen.iter()))
+        self.chunks.iter().map(|(chunk_pos, chunk_gen, edits)| {
+            (
+                *chunk_pos,
+                chunk_gen.iter().map(move |(position, tile)| {
+                    (position, edited(edits.as_ref(), position, tile))
+                }),
+            )
+        })
     }
 }
+
+/// The edited tile at the local position if there is one, otherwise the generated one.
+fn edited<'a, T>(
+    edits: Option<&'a ChunkEdits<T>>,
+    position: Vector2<usize>,
+    tile: &'a Tile<T>,
+) -> &'a Tile<T> {
+    edits.and_then(|edits| edits.get(&position)).unwrap_or(tile)
+}
@@ -1,5 +1,12 @@
+mod config;
+mod coords;
 mod generator;
+mod hash;
 mod types;
 
+pub use config::*;
+pub use coords::*;
 pub use generator::*;
 pub use types::*;
+
+use hash::*;
@@ -1,6 +1,8 @@
 use std::ops::{Add, Div, Mul, Sub};
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Vector2<T> {
     pub x: T,
     pub y: T,
@@ -30,6 +32,17 @@ impl<T: Add> Add<Self> for Vector2<T> {
     }
 }
 
+impl<T: Sub> Sub<Self> for Vector2<T> {
+    type Output = Vector2<T::Output>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector2 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
 impl<T: Mul + Copy> Mul<T> for Vector2<T> {
     type Output = Vector2<T::Output>;
 
@@ -74,7 +87,7 @@ impl<T: Div> Div<Self> for Vector2<T> {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Area<T> {
     pub start: Vector2<T>,
     pub end: Vector2<T>,
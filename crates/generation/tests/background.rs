@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+mod common;
+
+use generation::*;
+
+const AREA: Area<f32> = Area {
+    start: Vector2::new(0.0, 0.0),
+    end: Vector2::new(1.0, 1.0),
+};
+
+fn world() -> WorldGenerator<&'static str> {
+    let mut world = common::world(DEFAULT_TILE_SIZE, DEFAULT_CHUNK_SIZE);
+    world.set_background_threads(Some(1));
+    world
+}
+
+/// Poll the world until nothing is pending, returns the polled events.
+fn wait(
+    world: &mut WorldGenerator<&'static str>,
+    events: &std::sync::mpsc::Receiver<ChunkEvent>,
+) -> Vec<ChunkEvent> {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while world.pending_chunks() > 0 {
+        assert!(
+            Instant::now() < deadline,
+            "The background generation is stuck"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        world.poll_generated();
+    }
+    world.poll_generated();
+    events.try_iter().collect()
+}
+
+#[test]
+fn panic_fails_only_the_chunk() {
+    let mut world = world();
+    let events = world.subscribe();
+    // Too big to be allocated, so the generation of the chunk panics
+    world.set_chunk_size(Vector2::new(usize::MAX / 2, 1));
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    let failed = wait(&mut world, &events);
+    assert_eq!(
+        failed.iter().map(|event| event.kind).collect::<Vec<_>>(),
+        [ChunkEventKind::Failed]
+    );
+
+    // Failed chunks are not requested again
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    assert_eq!(world.pending_chunks(), 0);
+
+    // Unless they are cancelled
+    world.cancel_requests_outside(Area {
+        start: Vector2::new(0.0, 1000.0),
+        end: Vector2::new(1.0, 1001.0),
+    });
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    assert_eq!(world.pending_chunks(), 1);
+    let failed = wait(&mut world, &events);
+    assert_eq!(
+        failed.iter().map(|event| event.kind).collect::<Vec<_>>(),
+        [ChunkEventKind::Failed]
+    );
+
+    // Or the generator changes, even if the tiles stay the same
+    world
+        .generator_mut()
+        .add_noise("Humidity", 1, common::noise(10.0))
+        .unwrap();
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    assert_eq!(world.pending_chunks(), 1);
+    wait(&mut world, &events);
+
+    // The worker is still alive
+    world.set_chunk_size(Vector2::new(4, 4));
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    let generated = wait(&mut world, &events);
+    assert!(generated
+        .iter()
+        .any(|event| event.kind == ChunkEventKind::Generated));
+    assert_eq!(world.view(AREA).chunks().count(), 1);
+}
+
+#[test]
+fn requested_chunks_are_not_evicted() {
+    let mut world = world();
+    world.set_chunk_size(Vector2::new(4, 4));
+    world.set_cache_policy(CachePolicy {
+        max_chunks: Some(2),
+        max_bytes: None,
+        eviction: Eviction::LeastRecentlyUsed,
+    });
+    let events = world.subscribe();
+    // 3x3 chunks of 8x8 world units
+    let area = Area {
+        start: Vector2::new(0.0, 0.0),
+        end: Vector2::new(20.0, 20.0),
+    };
+    world.request_area(area, Vector2::new(0.0, 0.0));
+    let events = wait(&mut world, &events);
+    assert!(events
+        .iter()
+        .all(|event| event.kind == ChunkEventKind::Generated));
+    assert_eq!(world.view(area).chunks().count(), 9);
+}
@@ -0,0 +1,246 @@
+use generation::*;
+
+fn noise(min_value: f32, max_value: f32) -> MultiNoiseProperties {
+    MultiNoiseProperties {
+        min_value,
+        max_value,
+        scale: 10.0,
+        octaves: 1,
+        lacunarity: 2.0,
+        persistance: 0.5,
+    }
+}
+
+#[test]
+fn builder_collects_all_problems() {
+    let problems = WorldGenerator::builder()
+        .tile_size(Vector2::new(0.0, 1.0))
+        .noise("Height", 0, noise(1.0, -1.0))
+        .noise("Height", 1, noise(-1.0, 1.0))
+        .erosion("Missing", ErosionProperties::default())
+        .generation_tree(
+            GenerationTree::new("Land", TileGeneration::new(vec![("Height", 0.0..=1.0)])).child(
+                GenerationTree::new("Swamp", TileGeneration::new(vec![("Humidity", 0.0..=1.0)])),
+            ),
+        )
+        .build()
+        .err()
+        .unwrap();
+
+    let messages: Vec<_> = problems.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "Invalid tile size (0, 1), it must be positive",
+            "noises[0]: Noise Height has invalid values from 1 to -1",
+            "noises[1]: Noise named Height already exists",
+            "erosion: Parameter named Missing not found",
+            "generations[0].children[0]: Parameter named Humidity not found",
+        ]
+    );
+}
+
+#[test]
+fn tile_size_keeps_supersampling() {
+    let supersampling = Supersampling::Majority { samples: 2 };
+    let world: WorldGenerator<()> = WorldGenerator::builder()
+        .scale(GenerationScale::Supersampled {
+            x: 4.0,
+            y: 4.0,
+            supersampling,
+        })
+        .tile_size(Vector2::new(8.0, 8.0))
+        .build()
+        .unwrap();
+    assert_eq!(world.tile_size(), Vector2::new(8.0, 8.0));
+    assert_eq!(world.supersampling(), supersampling);
+}
+
+#[test]
+fn config_problems_name_the_generations() {
+    #[derive(Debug)]
+    struct Biome;
+
+    impl std::str::FromStr for Biome {
+        type Err = ();
+
+        fn from_str(id: &str) -> Result<Self, ()> {
+            match id {
+                "Forest" | "Clearing" => Ok(Biome),
+                _ => Err(()),
+            }
+        }
+    }
+
+    let config = GeneratorConfig::parse(
+        r#"(
+            tile_size: (x: 2.0, y: 2.0),
+            noises: [
+                (
+                    name: "Height",
+                    seed: 0,
+                    properties: (
+                        min_value: -1.0,
+                        max_value: 1.0,
+                        scale: 10.0,
+                        octaves: 1,
+                        lacunarity: 2.0,
+                        persistance: 0.5,
+                    ),
+                ),
+            ],
+            generations: [
+                (id: "Volcano", parameters: {"Height": (min: 0.5, max: 1.0)}),
+                (
+                    id: "Forest",
+                    parameters: {"Height": (min: 0.0, max: 1.0)},
+                    children: [(id: "Clearing", parameters: {"Height": (min: 0.8, max: 0.6)})],
+                ),
+            ],
+        )"#,
+    )
+    .unwrap();
+
+    let problems = match config.build::<Biome>() {
+        Err(ConfigError::Invalid { problems, .. }) => problems,
+        result => panic!("Expected the config to be invalid, got {:?}", result.err()),
+    };
+    let messages: Vec<_> = problems.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            r#"generations[0] ("Volcano"): Unknown generation id"#,
+            r#"generations[1].children[0] ("Clearing"): Parameter Height has invalid range from 0.8 to 0.6"#,
+        ]
+    );
+}
+
+#[test]
+fn loaded_config_problems_name_the_file() {
+    let path = std::env::temp_dir().join("generation-invalid-config.ron");
+    std::fs::write(
+        &path,
+        r#"(tile_size: (x: 2.0, y: 2.0), noises: [], generations: [(id: "Forest", parameters: {"Height": (min: 0.0, max: 1.0)})])"#,
+    )
+    .unwrap();
+    let config = GeneratorConfig::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let error = config.build::<String>().err().unwrap();
+    let expected = format!(
+        r#"{}: generations[0] ("Forest"): Too many generation parameters (1) or not enough noises (0)
+{}: generations[0] ("Forest"): Parameter named Height not found"#,
+        path.display(),
+        path.display()
+    );
+    assert_eq!(error.to_string(), expected);
+}
+
+#[test]
+fn erosion_properties_are_checked() {
+    let properties = ErosionProperties {
+        padding: 10,
+        droplet_lifetime: 30,
+        droplet_density: 2.0,
+        gravity: f32::NAN,
+        ..Default::default()
+    };
+    let builder = WorldGenerator::<()>::builder()
+        .noise("Height", 0, noise(-1.0, 1.0))
+        .erosion("Height", properties.clone());
+    let messages: Vec<_> = builder.validate().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "erosion: Erosion has invalid droplet_density 2, it must be from 0 to 1",
+            "erosion: Erosion has invalid gravity NaN, it must be finite and not negative",
+            "erosion: Erosion padding 10 is smaller than the droplet lifetime plus the thermal iterations 35",
+        ]
+    );
+    assert_eq!(builder.build().err().unwrap().len(), 3);
+
+    let mut world: WorldGenerator<()> = WorldGenerator::builder()
+        .noise("Height", 0, noise(-1.0, 1.0))
+        .build()
+        .unwrap();
+    assert!(world.generator().validate().is_empty());
+    assert!(matches!(
+        world.generator_mut().set_erosion("Height", properties),
+        Err(GenerationError::InvalidErosionProperty {
+            property: "droplet_density",
+            ..
+        })
+    ));
+    world
+        .generator_mut()
+        .set_erosion("Height", ErosionProperties::default())
+        .unwrap();
+    assert!(world.generator().validate().is_empty());
+}
+
+#[test]
+fn reversed_ranges_are_rejected() {
+    let reversed = || TileGeneration::new(vec![("Height", 1.0..=0.0)]);
+    let problems = WorldGenerator::builder()
+        .noise("Height", 0, noise(-1.0, 1.0))
+        .generation("Land", reversed())
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        &problems[..],
+        [BuildError::Generation {
+            error: GenerationError::InvalidParameterRange { .. },
+            ..
+        }]
+    ));
+
+    let mut world: WorldGenerator<&str> = WorldGenerator::builder()
+        .noise("Height", 0, noise(-1.0, 1.0))
+        .build()
+        .unwrap();
+    assert!(matches!(
+        world.generator_mut().add_generation("Land", reversed()),
+        Err(GenerationError::InvalidParameterRange { .. })
+    ));
+    assert!(world.generator().validate().is_empty());
+}
+
+#[test]
+fn build_errors_are_errors() -> Result<(), Box<dyn std::error::Error>> {
+    let build = |samples| {
+        WorldGenerator::<()>::builder()
+            .scale(GenerationScale::Supersampled {
+                x: 1.0,
+                y: 1.0,
+                supersampling: Supersampling::Majority { samples },
+            })
+            .build()
+    };
+    let problems = build(0).err().unwrap();
+    assert_eq!(
+        problems.to_string(),
+        "Invalid number of samples 0, it must be from 1 to 16"
+    );
+    assert!(build(MAX_SUPERSAMPLES + 1).is_err());
+
+    let world = build(MAX_SUPERSAMPLES)?;
+    assert_eq!(
+        world.supersampling(),
+        Supersampling::Majority {
+            samples: MAX_SUPERSAMPLES
+        }
+    );
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Supersampling must take from 1 to 16 samples, got 17")]
+fn too_many_samples_are_rejected() {
+    let mut world: WorldGenerator<()> = WorldGenerator::new();
+    world.set_scale(GenerationScale::Supersampled {
+        x: 1.0,
+        y: 1.0,
+        supersampling: Supersampling::Average { samples: 17 },
+    });
+}
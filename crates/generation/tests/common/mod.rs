@@ -0,0 +1,33 @@
+//! The worlds shared by the tests.
+#![allow(dead_code)]
+
+use generation::*;
+
+/// A noise from -1 to 1 with a single octave.
+pub fn noise(scale: f32) -> MultiNoiseProperties {
+    MultiNoiseProperties {
+        min_value: -1.0,
+        max_value: 1.0,
+        scale,
+        octaves: 1,
+        lacunarity: 2.0,
+        persistance: 0.5,
+    }
+}
+
+/// A builder with the "Height" noise and the given sizes of the tiles and the chunks.
+pub fn builder<T>(tile_size: Vector2<f32>, chunk_size: Vector2<usize>) -> WorldGeneratorBuilder<T> {
+    WorldGenerator::builder()
+        .tile_size(tile_size)
+        .chunk_size(chunk_size)
+        .noise("Height", 0, noise(10.0))
+}
+
+/// Land where the height is positive, and water everywhere else.
+pub fn world(tile_size: Vector2<f32>, chunk_size: Vector2<usize>) -> WorldGenerator<&'static str> {
+    builder(tile_size, chunk_size)
+        .generation("Land", TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .fallback("Water")
+        .build()
+        .unwrap()
+}
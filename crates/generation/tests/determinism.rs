@@ -0,0 +1,253 @@
+//! Checks the determinism guarantee described in [GENERATION_VERSION].
+//!
+//! The reference worlds are generated and every chunk is hashed byte by byte
+//! (the tiles together with the retained parameter values). The hashes are compared
+//! with the ones recorded in `tests/golden/chunks.ron` for the current version.
+//!
+//! If a change alters the generated chunks on purpose, increase [GENERATION_VERSION]
+//! and record the new hashes with:
+//! ```sh
+//! UPDATE_GOLDEN=1 cargo test -p generation --test determinism
+//! ```
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use generation::*;
+use serde::{Deserialize, Serialize};
+
+const REFERENCE_CONFIG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/reference.ron");
+const GOLDEN_CHUNKS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/chunks.ron");
+
+const CHUNK_SIZE: Vector2<usize> = Vector2::new(16, 16);
+
+/// The generated area, in world units. Covers the negative coordinates too.
+const AREA: Area<f32> = Area {
+    start: Vector2::new(-50.0, -30.0),
+    end: Vector2::new(30.0, 50.0),
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Biome {
+    Ocean,
+    Beach,
+    Forest,
+    MagicForest,
+    Lake,
+    Hills,
+}
+
+impl FromStr for Biome {
+    type Err = ();
+
+    fn from_str(id: &str) -> Result<Self, ()> {
+        match id {
+            "Ocean" => Ok(Biome::Ocean),
+            "Beach" => Ok(Biome::Beach),
+            "Forest" => Ok(Biome::Forest),
+            "MagicForest" => Ok(Biome::MagicForest),
+            "Lake" => Ok(Biome::Lake),
+            "Hills" => Ok(Biome::Hills),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The recorded hashes of the chunks.
+#[derive(Debug, Serialize, Deserialize)]
+struct Golden {
+    /// The [GENERATION_VERSION], with which the hashes were recorded.
+    version: u32,
+    /// The hashes of the chunks by their positions, for every reference world.
+    worlds: BTreeMap<String, BTreeMap<(i32, i32), String>>,
+}
+
+/// The world described by the reference config, with erosion.
+fn reference_world() -> WorldGenerator<Biome> {
+    let config = GeneratorConfig::load(REFERENCE_CONFIG).unwrap();
+    let mut world: WorldGenerator<Biome> = config.build().unwrap();
+    world.set_chunk_size(CHUNK_SIZE);
+    world.set_retain_parameters(true);
+    world
+}
+
+/// A world without erosion, described with the builder.
+fn seeded_world() -> WorldGenerator<Biome> {
+    let noise = |min_value, max_value, scale, octaves| MultiNoiseProperties {
+        min_value,
+        max_value,
+        scale,
+        octaves,
+        lacunarity: 2.0,
+        persistance: 0.5,
+    };
+    WorldGenerator::builder()
+        .seed(1234)
+        .chunk_size(CHUNK_SIZE)
+        .retain_parameters(true)
+        .noise("Height", 0, noise(-5.0, 10.0, 80.0, 4))
+        .noise("Humidity", 1, noise(0.0, 1.0, 40.0, 1))
+        .generation(
+            Biome::Beach,
+            TileGeneration::new(vec![("Height", 0.0..=1.0)]),
+        )
+        .generation_tree(
+            GenerationTree::new(
+                Biome::Forest,
+                TileGeneration::new(vec![("Height", 1.0..=10.0)]),
+            )
+            .child(GenerationTree::new(
+                Biome::Lake,
+                TileGeneration::new(vec![("Humidity", 0.8..=1.0)]),
+            )),
+        )
+        .fallback(Biome::Ocean)
+        .build()
+        .unwrap()
+}
+
+/// The reference worlds by their names.
+fn reference_worlds() -> Vec<(&'static str, WorldGenerator<Biome>)> {
+    let mut majority = reference_world();
+    majority.set_scale(GenerationScale::Supersampled {
+        x: 8.0,
+        y: 8.0,
+        supersampling: Supersampling::Majority { samples: 4 },
+    });
+    let mut average = reference_world();
+    average.set_scale(GenerationScale::Supersampled {
+        x: 8.0,
+        y: 8.0,
+        supersampling: Supersampling::Average { samples: 2 },
+    });
+    vec![
+        ("reference", reference_world()),
+        ("majority", majority),
+        ("average", average),
+        ("seeded", seeded_world()),
+    ]
+}
+
+/// The hashes of all generated chunks covering [AREA].
+fn chunk_hashes(world: &WorldGenerator<Biome>) -> BTreeMap<(i32, i32), String> {
+    world
+        .view(AREA)
+        .chunks()
+        .map(|(chunk_pos, _)| {
+            let view = world.view_tiles(chunk_pos.tiles(CHUNK_SIZE));
+            let mut bytes = Vec::new();
+            for (_, tile, parameters) in view.tiles_with_parameters() {
+                bytes.extend(bincode::serialize(tile).unwrap());
+                for value in parameters.expect("Parameters are not retained").as_slice() {
+                    bytes.extend(value.to_le_bytes());
+                }
+            }
+            (
+                (chunk_pos.x, chunk_pos.y),
+                format!("{:016x}", fnv1a(&bytes)),
+            )
+        })
+        .collect()
+}
+
+/// Does not depend on the standard library, unlike [std::collections::hash_map::DefaultHasher].
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[test]
+fn generated_chunks_match_golden() {
+    let actual = Golden {
+        version: GENERATION_VERSION,
+        worlds: reference_worlds()
+            .into_iter()
+            .map(|(name, mut world)| {
+                world.generate_area(AREA, 0.0);
+                (name.to_owned(), chunk_hashes(&world))
+            })
+            .collect(),
+    };
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let golden = ron::ser::to_string_pretty(&actual, ron::ser::PrettyConfig::default());
+        std::fs::write(GOLDEN_CHUNKS, golden.unwrap() + "\n").unwrap();
+        return;
+    }
+
+    let expected: Golden = ron::from_str(&std::fs::read_to_string(GOLDEN_CHUNKS).unwrap()).unwrap();
+    assert_eq!(
+        expected.version, GENERATION_VERSION,
+        "The golden hashes are recorded for another version, record them again"
+    );
+    for (name, expected) in &expected.worlds {
+        let actual = &actual.worlds[name];
+        let changed: Vec<_> = expected
+            .iter()
+            .filter(|&(chunk_pos, hash)| actual.get(chunk_pos) != Some(hash))
+            .map(|(chunk_pos, _)| chunk_pos)
+            .collect();
+        assert!(
+            changed.is_empty() && expected.len() == actual.len(),
+            "The chunks {:?} of the {:?} world have changed. If that is intended, \
+             increase GENERATION_VERSION and record the hashes again",
+            changed,
+            name
+        );
+    }
+    assert_eq!(expected.worlds.len(), actual.worlds.len());
+}
+
+#[test]
+fn thread_count_does_not_change_chunks() {
+    let mut expected = reference_world();
+    expected.generate_area(AREA, 0.0);
+    let expected = chunk_hashes(&expected);
+
+    for threads in [1, 3] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let mut world = reference_world();
+        world.set_thread_pool(Some(Arc::new(pool)));
+        world.generate_area(AREA, 0.0);
+        assert_eq!(chunk_hashes(&world), expected, "{} threads", threads);
+    }
+
+    let mut world = reference_world();
+    world.set_background_threads(Some(2));
+    world.request_area(AREA, Vector2::new(0.0, 0.0));
+    let mut generated = 0;
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while world.pending_chunks() > 0 {
+        assert!(
+            Instant::now() < deadline,
+            "The background generation is stuck"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        generated += world.poll_generated().len();
+    }
+    generated += world.poll_generated().len();
+    assert_eq!(generated, expected.len());
+    assert_eq!(chunk_hashes(&world), expected, "Background generation");
+}
+
+#[test]
+fn chunk_size_is_fingerprinted_with_erosion() {
+    let mut eroded = reference_world();
+    let fingerprint = eroded.fingerprint();
+    eroded.set_chunk_size(CHUNK_SIZE * 2);
+    assert_ne!(eroded.fingerprint(), fingerprint);
+
+    let mut seeded = seeded_world();
+    let fingerprint = seeded.fingerprint();
+    seeded.set_chunk_size(CHUNK_SIZE * 2);
+    assert_eq!(seeded.fingerprint(), fingerprint);
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use generation::*;
+
+const LAKE: Option<TileValue<&str>> = Some(TileValue {
+    parent: "Lake",
+    child: None,
+});
+
+fn world() -> WorldGenerator<&'static str> {
+    common::world(Vector2::new(2.0, 2.0), Vector2::new(4, 4))
+}
+
+/// The tiles of the current scale from `start` to `end` exclusive, that are lakes.
+fn lakes(world: &mut WorldGenerator<&'static str>, start: TilePos, end: TilePos) -> Vec<TilePos> {
+    let area = Area { start, end };
+    let mut lakes: Vec<_> = world
+        .generate_tiles(area)
+        .grid_tiles()
+        .filter(|&(tile, value)| {
+            tile.x >= start.x
+                && tile.x < end.x
+                && tile.y >= start.y
+                && tile.y < end.y
+                && *value == LAKE
+        })
+        .map(|(tile, _)| tile)
+        .collect();
+    lakes.sort_by_key(|tile| (tile.y, tile.x));
+    lakes
+}
+
+fn set_scale(world: &mut WorldGenerator<&'static str>, size: f32, supersampling: Supersampling) {
+    world.set_scale(GenerationScale::Supersampled {
+        x: size,
+        y: size,
+        supersampling,
+    });
+}
+
+#[test]
+fn edits_stay_in_place_with_finer_scales() {
+    let mut world = world();
+    world.set_tile(Vector2::new(-1, 2), LAKE);
+
+    set_scale(&mut world, 1.0, Supersampling::None);
+    assert_eq!(
+        lakes(&mut world, Vector2::new(-8, -8), Vector2::new(8, 8)),
+        [
+            Vector2::new(-2, 4),
+            Vector2::new(-1, 4),
+            Vector2::new(-2, 5),
+            Vector2::new(-1, 5),
+        ]
+    );
+    assert_eq!(world.get_tile(Vector2::new(-2, 5)), LAKE);
+
+    // Back to the scale of the edit
+    set_scale(&mut world, 2.0, Supersampling::None);
+    assert_eq!(
+        lakes(&mut world, Vector2::new(-4, -4), Vector2::new(4, 4)),
+        [Vector2::new(-1, 2)]
+    );
+}
+
+#[test]
+fn coarse_tiles_are_edited_like_they_are_sampled() {
+    let mut world = world();
+    set_scale(&mut world, 1.0, Supersampling::None);
+    // The corner of the coarse tile (0, 0)
+    world.set_tile(Vector2::new(0, 0), LAKE);
+    // Inside of the coarse tile (2, 0), but not its corner
+    world.set_tile(Vector2::new(5, 1), LAKE);
+
+    set_scale(&mut world, 2.0, Supersampling::None);
+    assert_eq!(
+        lakes(&mut world, Vector2::new(-4, -4), Vector2::new(4, 4)),
+        [Vector2::new(0, 0)]
+    );
+
+    // With supersampling the coarse tile is edited if most of its samples are
+    set_scale(&mut world, 1.0, Supersampling::None);
+    world.set_tile(Vector2::new(4, 0), LAKE);
+    world.set_tile(Vector2::new(4, 1), LAKE);
+    set_scale(&mut world, 2.0, Supersampling::Majority { samples: 2 });
+    assert_eq!(
+        lakes(&mut world, Vector2::new(-4, -4), Vector2::new(4, 4)),
+        [Vector2::new(2, 0)]
+    );
+}
+
+#[test]
+fn levels_of_detail_are_edited() {
+    let mut world = world();
+    for y in 0..2 {
+        for x in 0..2 {
+            world.set_tile(Vector2::new(x, y), LAKE);
+        }
+    }
+    let area = Area {
+        start: Vector2::new(0.0, 0.0),
+        end: Vector2::new(1.0, 1.0),
+    };
+    let view = world.generate_area(area, 4.0);
+    assert_eq!(view.tile_size, Vector2::new(4.0, 4.0));
+    let (_, tile) = view
+        .grid_tiles()
+        .find(|&(tile, _)| tile == Vector2::new(0, 0))
+        .unwrap();
+    assert_eq!(*tile, LAKE);
+}
+
+#[test]
+fn edits_are_kept_and_reverted() {
+    let mut world = world();
+    world.set_tile(Vector2::new(1, 1), LAKE);
+    world.set_tile(Vector2::new(1, 1), None);
+    assert_eq!(world.edits().count(), 1);
+
+    // The chunk size does not move the edits
+    world.set_chunk_size(Vector2::new(3, 5));
+    assert_eq!(world.edited_tile(Vector2::new(1, 1)), Some(&None));
+
+    // Reverting a finer tile reverts the coarse edit overlapping it
+    set_scale(&mut world, 1.0, Supersampling::None);
+    let reverted = world.revert_tile(Vector2::new(3, 2));
+    assert_eq!(
+        reverted,
+        [(TilePos::new(1, 1).world_area(Vector2::new(2.0, 2.0)), None)]
+    );
+    assert_eq!(world.edits().count(), 0);
+    assert_eq!(world.edited_tile(Vector2::new(2, 2)), None);
+}
+
+#[test]
+fn coarse_levels_of_detail_are_viewed_quickly() {
+    let mut world = world();
+    let area = Area {
+        start: Vector2::new(0.0, 0.0),
+        end: Vector2::new(1.0, 1.0),
+    };
+    let resolution = world.lod_tile_size(MAX_LOD_LEVEL).x;
+    assert_eq!(world.lod_level(resolution), MAX_LOD_LEVEL);
+    // Without edits and with a tiny one the view does not walk the whole chunk
+    assert!(world.generate_area(area, resolution).chunks().count() > 0);
+    world.set_tile(Vector2::new(1, 0), LAKE);
+    assert_ne!(tile_at_origin(&mut world, area, resolution), LAKE);
+
+    // An edit made with a coarse scale still shows up
+    set_scale(&mut world, 512.0, Supersampling::None);
+    world.set_tile(Vector2::new(0, 0), LAKE);
+    set_scale(&mut world, 2.0, Supersampling::None);
+    assert_eq!(tile_at_origin(&mut world, area, resolution), LAKE);
+}
+
+fn tile_at_origin(
+    world: &mut WorldGenerator<&'static str>,
+    area: Area<f32>,
+    resolution: f32,
+) -> Option<TileValue<&'static str>> {
+    let view = world.generate_area(area, resolution);
+    let (_, &tile) = view
+        .grid_tiles()
+        .find(|&(tile, _)| tile == Vector2::new(0, 0))
+        .unwrap();
+    tile
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use generation::*;
+
+const TILES: i64 = 96;
+
+fn world(chunk_size: usize) -> WorldGenerator<&'static str> {
+    WorldGenerator::builder()
+        .chunk_size(Vector2::new(chunk_size, chunk_size))
+        .retain_parameters(true)
+        .noise(
+            "Height",
+            0,
+            MultiNoiseProperties {
+                min_value: -1.0,
+                max_value: 1.0,
+                scale: 20.0,
+                octaves: 3,
+                lacunarity: 2.0,
+                persistance: 0.5,
+            },
+        )
+        .erosion("Height", ErosionProperties::default())
+        .generation("Land", TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .fallback("Water")
+        .build()
+        .unwrap()
+}
+
+/// The eroded heights of the tiles from 0 to [TILES] along each axis.
+fn heights(chunk_size: usize) -> HashMap<TilePos, f32> {
+    let mut world = world(chunk_size);
+    let height = world.generator().parameter("Height").unwrap();
+    let tile_size = world.tile_size();
+    let view = world.generate_tiles(Area {
+        start: Vector2::new(0, 0),
+        end: Vector2::new(TILES, TILES),
+    });
+    view.tiles_with_parameters()
+        .map(|(area, _, parameters)| {
+            let tile = TilePos::from_world(area.start + tile_size / 2.0, tile_size);
+            (tile, parameters.unwrap().get(height).unwrap())
+        })
+        .filter(|(tile, _)| tile.x < TILES && tile.y < TILES)
+        .collect()
+}
+
+/// Mean absolute difference between the neighbouring tiles along x,
+/// across the borders of the chunks and inside of them.
+fn steps(heights: &HashMap<TilePos, f32>, chunk_size: i64) -> (f32, f32) {
+    let (mut border, mut inside) = (Vec::new(), Vec::new());
+    for y in 0..TILES {
+        for x in 0..TILES - 1 {
+            let step = (heights[&Vector2::new(x, y)] - heights[&Vector2::new(x + 1, y)]).abs();
+            if (x + 1) % chunk_size == 0 {
+                border.push(step);
+            } else {
+                inside.push(step);
+            }
+        }
+    }
+    let mean = |steps: Vec<f32>| steps.iter().sum::<f32>() / steps.len() as f32;
+    (mean(border), mean(inside))
+}
+
+#[test]
+fn chunk_borders_are_continuous() {
+    let chunk_size = 16;
+    let chunked = heights(chunk_size);
+    // The whole area is eroded at once
+    let whole = heights(128);
+
+    // The borders of the chunks are not steeper than their insides
+    let (border, inside) = steps(&chunked, chunk_size as i64);
+    assert!(
+        border <= inside * 1.1,
+        "Seams: {} on the borders, {} inside",
+        border,
+        inside
+    );
+
+    // The chunk size changes the heights only a little on average
+    let difference = chunked
+        .iter()
+        .map(|(tile, height)| (height - whole[tile]).abs())
+        .sum::<f32>()
+        / chunked.len() as f32;
+    assert!(difference < 0.05, "Difference {}", difference);
+}
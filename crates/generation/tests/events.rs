@@ -0,0 +1,138 @@
+use std::sync::mpsc::Receiver;
+
+mod common;
+
+use generation::*;
+
+const AREA: Area<f32> = Area {
+    start: Vector2::new(0.0, 0.0),
+    end: Vector2::new(10.0, 10.0),
+};
+
+fn world() -> WorldGenerator<u8> {
+    common::builder(DEFAULT_TILE_SIZE, Vector2::new(4, 4))
+        .generation(1, TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .build()
+        .unwrap()
+}
+
+/// The kinds of the received events, checking that all of them are of the chunks
+/// of the current scale.
+fn kinds(world: &WorldGenerator<u8>, events: &Receiver<ChunkEvent>) -> Vec<ChunkEventKind> {
+    events
+        .try_iter()
+        .map(|event| {
+            assert_eq!(event.tile_size, world.tile_size());
+            assert_eq!(event.supersampling, world.supersampling());
+            event.kind
+        })
+        .collect()
+}
+
+#[test]
+fn generated_chunks_are_reported() {
+    let mut world = world();
+    let events = world.subscribe();
+    world.generate_area(AREA, 0.0);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Generated; world.chunk_count()]
+    );
+
+    // The kept chunks are not generated again
+    world.generate_area(AREA, 0.0);
+    assert!(kinds(&world, &events).is_empty());
+}
+
+#[test]
+fn stored_chunks_are_loaded() {
+    let directory =
+        std::env::temp_dir().join(format!("generation-events-storage-{}", std::process::id()));
+    let mut world = world();
+    let storage = ChunkStorage::new(&directory, world.fingerprint());
+    world.set_storage(Some(storage));
+    world.generate_area(AREA, 0.0);
+    let generated = world.chunk_count();
+
+    let events = world.subscribe();
+    world.unload_area(AREA);
+    assert_eq!(world.chunk_count(), 0);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Evicted; generated]
+    );
+
+    world.generate_area(AREA, 0.0);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Loaded; generated]
+    );
+    assert!(world.take_storage_errors().is_empty());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn edits_are_reported() {
+    let mut world = world();
+    world.generate_area(AREA, 0.0);
+    let events = world.subscribe();
+    let edited = Some(TileValue {
+        parent: 2,
+        child: None,
+    });
+
+    world.set_tile(Vector2::new(5, 6), edited);
+    let modified: Vec<_> = events.try_iter().collect();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0].kind, ChunkEventKind::Modified);
+    assert_eq!(modified[0].chunk_pos, Vector2::new(1, 1));
+
+    // A chunk that is not generated is modified too
+    world.set_tile(Vector2::new(-1, 0), edited);
+    let modified: Vec<_> = events.try_iter().collect();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0].chunk_pos, Vector2::new(-1, 0));
+
+    assert_eq!(world.revert_tile(Vector2::new(5, 6)).len(), 1);
+    let modified: Vec<_> = events.try_iter().collect();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0].kind, ChunkEventKind::Modified);
+    assert_eq!(modified[0].chunk_pos, Vector2::new(1, 1));
+
+    // Nothing is reverted
+    world.revert_tile(Vector2::new(5, 6));
+    assert!(kinds(&world, &events).is_empty());
+}
+
+#[test]
+fn evicted_chunks_are_reported() {
+    let mut world = world();
+    world.generate_area(AREA, 0.0);
+    let generated = world.chunk_count();
+    let events = world.subscribe();
+
+    world.set_cache_policy(CachePolicy {
+        max_chunks: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(world.chunk_count(), 1);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Evicted; generated - 1]
+    );
+
+    world.set_cache_policy(CachePolicy::default());
+    world.generate_area(AREA, 0.0);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Generated; generated - 1]
+    );
+
+    let generator = world.generator().clone();
+    world.set_generator(generator);
+    assert_eq!(world.chunk_count(), 0);
+    assert_eq!(
+        kinds(&world, &events),
+        vec![ChunkEventKind::Evicted; generated]
+    );
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use generation::*;
+
+fn world() -> WorldGenerator<&'static str> {
+    WorldGenerator::builder()
+        .chunk_size(Vector2::new(8, 8))
+        .noise("Height", 0, common::noise(20.0))
+        .generation("Land", TileGeneration::new(vec![("Height", 0.3..=1.0)]))
+        .generation("Peak", TileGeneration::new(vec![("Height", 2.0..=2.0)]))
+        .fallback("Water")
+        .build()
+        .unwrap()
+}
+
+fn is(generation: &'static str) -> impl Fn(&PointSample<&str>) -> bool {
+    move |sample| sample.tile.is_some_and(|tile| tile.parent == generation)
+}
+
+#[test]
+fn nearest_generation_is_located() {
+    let world = world();
+    let origin = Vector2::new(0.5, 0.5);
+    let located = world.locate(origin, 100.0, is("Land")).unwrap();
+    assert_eq!(located.sample.tile.unwrap().parent, "Land");
+
+    // No tile closer than the located one is land
+    let radius = located.distance.ceil() as i64 + 1;
+    let nearest = (-radius..=radius)
+        .flat_map(|y| (-radius..=radius).map(move |x| TilePos::new(x, y)))
+        .filter(|&tile| {
+            world
+                .get_tile(tile)
+                .is_some_and(|tile| tile.parent == "Land")
+        })
+        .map(|tile| {
+            let delta = tile.world_center(world.tile_size()) - origin;
+            (delta.x * delta.x + delta.y * delta.y).sqrt()
+        })
+        .fold(f32::INFINITY, f32::min);
+    assert_eq!(located.distance, nearest);
+}
+
+#[test]
+fn impossible_generation_is_not_located() {
+    let world = world();
+    assert!(world
+        .locate(Vector2::new(0.0, 0.0), 50.0, is("Peak"))
+        .is_none());
+    // Nothing is located beyond the radius
+    let land = world.locate(Vector2::new(0.0, 0.0), 100.0, is("Land"));
+    let distance = land.unwrap().distance;
+    assert!(world
+        .locate(Vector2::new(0.0, 0.0), distance * 0.5, is("Land"))
+        .is_none());
+}
+
+#[test]
+fn cached_chunks_do_not_change_the_result() {
+    let mut world = world();
+    let origin = Vector2::new(-3.0, 7.0);
+    let located = world.locate(origin, 100.0, is("Land")).unwrap();
+
+    world.generate_area(
+        Area {
+            start: Vector2::new(-50.0, -50.0),
+            end: Vector2::new(50.0, 50.0),
+        },
+        0.0,
+    );
+    let cached = world.locate(origin, 100.0, is("Land")).unwrap();
+    assert_eq!(cached.position, located.position);
+    assert_eq!(cached.distance, located.distance);
+    assert_eq!(cached.sample.tile, located.sample.tile);
+    assert_eq!(
+        cached.sample.parameters().as_slice(),
+        located.sample.parameters().as_slice()
+    );
+}
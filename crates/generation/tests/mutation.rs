@@ -0,0 +1,219 @@
+mod common;
+
+use common::noise;
+use generation::*;
+
+const AREA: Area<f32> = Area {
+    start: Vector2::new(0.0, 0.0),
+    end: Vector2::new(10.0, 10.0),
+};
+
+fn world() -> WorldGenerator<u8> {
+    common::builder(DEFAULT_TILE_SIZE, Vector2::new(4, 4))
+        .noise("Humidity", 1, noise(20.0))
+        .generation(1, TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .generation(2, TileGeneration::new(vec![("Humidity", 0.5..=1.0)]))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn changed_generator_evicts_the_chunks_at_once() {
+    let mut world = world();
+    world.generate_area(AREA, 0.0);
+    let generated = world.chunk_count();
+    let events = world.subscribe();
+
+    // Looking at the generator does not discard anything
+    assert_eq!(world.generator_mut().parameter_count(), 2);
+    assert_eq!(world.chunk_count(), generated);
+
+    world.generator_mut().set_fallback(Some(0));
+    assert_eq!(world.chunk_count(), 0);
+    assert_eq!(world.view(AREA).chunks().count(), 0);
+    let evicted: Vec<_> = events.try_iter().collect();
+    assert_eq!(evicted.len(), generated);
+    assert!(evicted
+        .iter()
+        .all(|event| event.kind == ChunkEventKind::Evicted));
+}
+
+#[test]
+fn changed_generator_keeps_the_storage() {
+    let directory = std::env::temp_dir().join(format!(
+        "generation-mutation-storage-{}",
+        std::process::id()
+    ));
+    let mut world = world();
+    let storage = ChunkStorage::new(&directory, world.fingerprint());
+    world.set_storage(Some(storage));
+    world.generate_area(AREA, 0.0);
+
+    world.generator_mut().set_fallback(Some(0));
+    let storage = world.storage().expect("The storage is detached");
+    assert_eq!(storage.config_hash(), world.fingerprint());
+    assert_eq!(storage.directory(), directory);
+
+    // The chunks of the changed generator are saved and loaded again
+    let events = world.subscribe();
+    world.generate_area(AREA, 0.0);
+    world.unload_area(AREA);
+    world.generate_area(AREA, 0.0);
+    assert!(events
+        .try_iter()
+        .any(|event| event.kind == ChunkEventKind::Loaded));
+    assert!(world.take_storage_errors().is_empty());
+
+    // The scale is a part of the key too
+    world.set_scale(GenerationScale::TileSize { x: 4.0, y: 4.0 });
+    assert_eq!(world.storage().unwrap().config_hash(), world.fingerprint());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn same_generators_have_the_same_fingerprint() {
+    let mut world = world();
+    let fingerprint = world.fingerprint();
+    let land = world.generator().generations().next().unwrap().id;
+    let mut generator = world.generator_mut();
+    generator.remove_generation(land).unwrap();
+    let land = generator
+        .add_generation(1, TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .unwrap();
+    generator.move_generation(land, 0).unwrap();
+    drop(generator);
+    // The ids are different, but the generators are the same
+    assert_eq!(world.fingerprint(), fingerprint);
+}
+
+fn tiles(world: &mut WorldGenerator<u8>) -> Vec<Option<TileValue<u8>>> {
+    world
+        .generate_area(AREA, 0.0)
+        .tiles()
+        .map(|(_, &tile)| tile)
+        .collect()
+}
+
+#[test]
+fn moved_noise_keeps_the_tiles() {
+    let mut world = world();
+    let before = tiles(&mut world);
+    let generated = world.chunk_count();
+
+    let humidity = world.generator().parameter("Humidity").unwrap();
+    world.generator_mut().move_noise(humidity, 0).unwrap();
+    assert_eq!(world.generator().parameter("Humidity").unwrap().index(), 0);
+    assert_eq!(world.generator().parameter("Height").unwrap().index(), 1);
+    assert_eq!(world.chunk_count(), generated);
+    assert_eq!(tiles(&mut world), before);
+
+    // The order of the retained parameter values changes
+    world.set_retain_parameters(true);
+    world.generate_area(AREA, 0.0);
+    let height = world.generator().parameter("Height").unwrap();
+    world.generator_mut().move_noise(height, 0).unwrap();
+    assert_eq!(world.chunk_count(), 0);
+}
+
+#[test]
+fn unused_noise_keeps_the_tiles() {
+    let mut world = world();
+    world.generate_area(AREA, 0.0);
+    let generated = world.chunk_count();
+    let unused = world
+        .generator_mut()
+        .add_noise("Temperature", 2, noise(30.0))
+        .unwrap();
+    world
+        .generator_mut()
+        .set_noise(unused, 3, noise(40.0))
+        .unwrap();
+    world.generator_mut().remove_noise(unused).unwrap();
+    assert_eq!(world.chunk_count(), generated);
+
+    let height = world.generator().parameter("Height").unwrap();
+    assert!(matches!(
+        world.generator_mut().remove_noise(height),
+        Err(GenerationError::NoiseInUse { .. })
+    ));
+    world
+        .generator_mut()
+        .set_noise(height, 3, noise(40.0))
+        .unwrap();
+    assert_eq!(world.chunk_count(), 0);
+}
+
+#[test]
+fn moving_past_the_end_is_out_of_range() {
+    let mut world = world();
+    let height = world.generator().parameter("Height").unwrap();
+    assert!(matches!(
+        world.generator_mut().move_noise(height, 2),
+        Err(GenerationError::IndexOutOfRange { index: 2, len: 2 })
+    ));
+    let land = world.generator().generations().next().unwrap().id;
+    assert!(matches!(
+        world.generator_mut().move_generation(land, 5),
+        Err(GenerationError::IndexOutOfRange { index: 5, len: 2 })
+    ));
+}
+
+#[test]
+fn generations_are_changed() {
+    let mut world = world();
+    let ids: Vec<_> = world
+        .generator()
+        .generations()
+        .map(|info| info.id)
+        .collect();
+    let (land, swamp) = (ids[0], ids[1]);
+    let bog = world
+        .generator_mut()
+        .add_sub_generation(swamp, 3, TileGeneration::new(vec![("Height", -1.0..=1.0)]))
+        .unwrap();
+    assert!(matches!(
+        world.generator_mut().move_generation(bog, 0),
+        Err(GenerationError::InvalidOrder { .. })
+    ));
+
+    // Every tile is land
+    let set_range = |world: &mut WorldGenerator<u8>, id, parameter, range| {
+        world
+            .generator_mut()
+            .set_generation_parameters(id, TileGeneration::new(vec![(parameter, range)]))
+            .unwrap();
+    };
+    set_range(&mut world, land, "Height", -1.0..=1.0);
+    set_range(&mut world, swamp, "Humidity", 1.0..=1.0);
+    assert!(tiles(&mut world)
+        .iter()
+        .all(|tile| tile.map(|tile| tile.parent) == Some(1)));
+    assert_eq!(
+        world.generator_mut().set_generation_value(land, 4).unwrap(),
+        1
+    );
+    assert!(tiles(&mut world)
+        .iter()
+        .all(|tile| tile.map(|tile| tile.parent) == Some(4)));
+
+    // Every tile is a bog in a swamp
+    world.generator_mut().move_generation(swamp, 0).unwrap();
+    set_range(&mut world, land, "Height", 1.0..=1.0);
+    set_range(&mut world, swamp, "Humidity", -1.0..=1.0);
+    let bog_tile = Some(TileValue {
+        parent: 2,
+        child: Some(3),
+    });
+    assert!(tiles(&mut world).iter().all(|&tile| tile == bog_tile));
+
+    // The sub-generations are removed with their parent
+    world.generator_mut().remove_generation(swamp).unwrap();
+    assert_eq!(
+        world
+            .generator()
+            .generations()
+            .map(|info| info.id)
+            .collect::<Vec<_>>(),
+        [land]
+    );
+}
@@ -0,0 +1,119 @@
+use generation::*;
+
+const CHUNK_SIZE: Vector2<usize> = Vector2::new(8, 8);
+
+fn world(retain_parameters: bool) -> WorldGenerator<&'static str> {
+    let noise = |scale| MultiNoiseProperties {
+        min_value: -1.0,
+        max_value: 1.0,
+        scale,
+        octaves: 2,
+        lacunarity: 2.0,
+        persistance: 0.5,
+    };
+    WorldGenerator::builder()
+        .chunk_size(CHUNK_SIZE)
+        .retain_parameters(retain_parameters)
+        .noise("Height", 0, noise(20.0))
+        .noise("Humidity", 1, noise(10.0))
+        .erosion("Height", ErosionProperties::default())
+        .generation("Land", TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+        .generation("Swamp", TileGeneration::new(vec![("Humidity", 0.5..=1.0)]))
+        .fallback("Water")
+        .build()
+        .unwrap()
+}
+
+fn positions() -> impl Iterator<Item = Vector2<f32>> {
+    (-10..10).flat_map(|y| (-10..10).map(move |x| Vector2::new(x as f32 * 1.5, y as f32 * 1.5)))
+}
+
+fn assert_same(actual: &PointSample<&str>, expected: &PointSample<&str>) {
+    assert_eq!(actual.tile, expected.tile);
+    assert_eq!(
+        actual.parameters().as_slice(),
+        expected.parameters().as_slice()
+    );
+}
+
+#[test]
+fn samples_of_chunks_without_parameters_match_retained_ones() {
+    let area = Area {
+        start: Vector2::new(-20.0, -20.0),
+        end: Vector2::new(20.0, 20.0),
+    };
+    let mut retained = world(true);
+    retained.generate_area(area, 0.0);
+    let mut discarded = world(false);
+    discarded.generate_area(area, 0.0);
+
+    for position in positions() {
+        assert_same(&discarded.sample(position), &retained.sample(position));
+    }
+}
+
+#[test]
+fn samples_without_chunks_match_generated_ones() {
+    let mut generated = world(false);
+    generated.generate_area(
+        Area {
+            start: Vector2::new(-20.0, -20.0),
+            end: Vector2::new(20.0, 20.0),
+        },
+        0.0,
+    );
+    let world = world(false);
+
+    let positions: Vec<_> = positions().collect();
+    let many = world.sample_many(&positions);
+    for (&position, sample) in positions.iter().zip(&many) {
+        assert_same(sample, &generated.sample(position));
+    }
+    // Every single sample erodes the chunk again, so only check a few
+    for &position in positions.iter().step_by(50) {
+        assert_same(&world.sample(position), &generated.sample(position));
+    }
+}
+
+#[test]
+fn samples_match_chunks_with_any_tile_size() {
+    let world = || -> WorldGenerator<&'static str> {
+        WorldGenerator::builder()
+            .tile_size(Vector2::new(0.3, 0.3))
+            .chunk_size(CHUNK_SIZE)
+            .noise(
+                "Height",
+                0,
+                MultiNoiseProperties {
+                    min_value: -1.0,
+                    max_value: 1.0,
+                    scale: 2.0,
+                    octaves: 3,
+                    lacunarity: 2.0,
+                    persistance: 0.5,
+                },
+            )
+            .generation("Land", TileGeneration::new(vec![("Height", 0.0..=1.0)]))
+            .fallback("Water")
+            .build()
+            .unwrap()
+    };
+    // Far from the origin the positions are rounded the most
+    let area = Area {
+        start: Vector2::new(1000.0, -1000.0),
+        end: Vector2::new(1040.0, -960.0),
+    };
+    let mut generated = world();
+    generated.set_retain_parameters(true);
+    let tiles: Vec<_> = generated
+        .generate_area(area, 0.0)
+        .grid_tiles()
+        .map(|(tile, &value)| (tile, value))
+        .collect();
+    let sampled = world();
+    for (tile, value) in tiles {
+        assert_eq!(sampled.get_tile(tile), value, "{:?}", tile);
+        let position = tile.world_center(sampled.tile_size());
+        assert_same(&sampled.sample(position), &generated.sample(position));
+    }
+}
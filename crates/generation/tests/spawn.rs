@@ -0,0 +1,61 @@
+mod common;
+
+use generation::*;
+
+fn world() -> WorldGenerator<&'static str> {
+    WorldGenerator::builder()
+        .chunk_size(Vector2::new(8, 8))
+        .noise("Height", 0, common::noise(20.0))
+        .generation("Land", TileGeneration::new(vec![("Height", 0.3..=1.0)]))
+        .generation("Beach", TileGeneration::new(vec![("Height", 0.0..=0.3)]))
+        .fallback("Water")
+        .build()
+        .unwrap()
+}
+
+fn criteria() -> SpawnCriteria<'static, &'static str> {
+    SpawnCriteria::new(|tile: &TileValue<&str>| tile.parent == "Beach")
+        .land(|tile| tile.parent != "Water")
+        .near(|tile| tile.parent == "Water", 2)
+        .min_land_in_radius(3, 10)
+        .min_island_size(20)
+        .search_radius(100)
+}
+
+#[test]
+fn spawn_is_the_same_every_time() {
+    let mut world = world();
+    let spawn = world.find_spawn(&criteria()).unwrap();
+    assert_eq!(
+        world.get_tile(TilePos::from_world(spawn, world.tile_size())),
+        Some(TileValue {
+            parent: "Beach",
+            child: None,
+        })
+    );
+    assert_eq!(self::world().find_spawn(&criteria()).unwrap(), spawn);
+
+    // The generated chunks do not change the spawn
+    world.generate_area(
+        Area {
+            start: Vector2::new(-100.0, -100.0),
+            end: Vector2::new(100.0, 100.0),
+        },
+        0.0,
+    );
+    assert_eq!(world.find_spawn(&criteria()).unwrap(), spawn);
+}
+
+#[test]
+fn impossible_spawn_is_not_found() {
+    let world = world();
+    let criteria = SpawnCriteria::new(|tile: &TileValue<&str>| tile.parent == "Beach")
+        .near(|_| false, 1)
+        .search_radius(10);
+    let error = world.find_spawn(&criteria).unwrap_err();
+    assert!(matches!(error, SpawnError::NotFound { search_radius: 10 }));
+    assert_eq!(
+        error.to_string(),
+        "No spawn point satisfies the criteria within 10 tiles along each axis from the origin"
+    );
+}
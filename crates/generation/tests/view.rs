@@ -0,0 +1,56 @@
+mod common;
+
+use generation::*;
+
+const TILE_SIZE: Vector2<f32> = Vector2::new(2.0, 3.0);
+const CHUNK_SIZE: Vector2<usize> = Vector2::new(4, 5);
+
+fn world() -> WorldGenerator<&'static str> {
+    common::world(TILE_SIZE, CHUNK_SIZE)
+}
+
+#[test]
+fn tile_areas_are_the_areas_of_the_tiles() {
+    let area = Area {
+        start: Vector2::new(-20.0, -20.0),
+        end: Vector2::new(20.0, 20.0),
+    };
+    let mut world = world();
+    world.generate_area(area, 0.0);
+    let view = world.view(area);
+
+    let mut tiles = 0;
+    for ((tile_area, _), (tile_pos, _)) in view.tiles().zip(view.grid_tiles()) {
+        assert_eq!(tile_area, tile_pos.world_area(TILE_SIZE), "{:?}", tile_pos);
+        tiles += 1;
+    }
+    assert_eq!(tiles, view.grid_tiles().count());
+    assert!(tiles > 0);
+}
+
+#[test]
+fn tile_areas_of_negative_chunks() {
+    let chunk_pos = ChunkPos::new(-1, -2);
+    let tiles = chunk_pos.tiles(CHUNK_SIZE);
+    let mut world = world();
+    world.generate_tiles(tiles);
+    let view = world.view_tiles(tiles);
+
+    let (first, _) = view.tiles().next().unwrap();
+    assert_eq!(
+        first,
+        Area {
+            start: Vector2::new(-8.0, -30.0),
+            end: Vector2::new(-6.0, -27.0),
+        }
+    );
+    let areas: Vec<_> = view.tiles().map(|(tile_area, _)| tile_area).collect();
+    assert_eq!(areas.len(), CHUNK_SIZE.x * CHUNK_SIZE.y);
+    let chunk_area = chunk_pos.world_area(CHUNK_SIZE, TILE_SIZE);
+    assert!(areas.iter().all(|tile_area| {
+        tile_area.start.x >= chunk_area.start.x
+            && tile_area.start.y >= chunk_area.start.y
+            && tile_area.end.x <= chunk_area.end.x
+            && tile_area.end.y <= chunk_area.end.y
+    }));
+}